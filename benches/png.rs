@@ -0,0 +1,169 @@
+//! Benchmarks for parsing, serializing, CRC verification and chunk lookup.
+//!
+//! Synthetic files range from 1 MB up to 16 MB by default. Set
+//! `PNGME_BENCH_LARGE=1` to also run the 256 MB and 1 GB scenarios.
+
+use std::str::FromStr;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use pngme::{scan, Chunk, ChunkType, Png};
+
+const MB: usize = 1024 * 1024;
+
+fn scenarios() -> Vec<(usize, usize)> {
+    let mut scenarios = vec![
+        (MB, 10),
+        (MB, 1_000),
+        (MB, 100_000),
+        (16 * MB, 10),
+        (16 * MB, 1_000),
+        (16 * MB, 100_000),
+    ];
+    if std::env::var_os("PNGME_BENCH_LARGE").is_some() {
+        scenarios.extend([(256 * MB, 10), (256 * MB, 100_000), (1024 * MB, 10)]);
+    }
+    scenarios
+}
+
+/// Builds a PNG of roughly `total_size` bytes split over `chunk_count` chunks.
+/// The last chunk has a distinct type so lookups have to walk every chunk.
+fn synthetic_png(total_size: usize, chunk_count: usize) -> Png {
+    let data_len = total_size / chunk_count;
+    let mut chunks: Vec<Chunk> = (0..chunk_count - 1)
+        .map(|i| {
+            Chunk::new(
+                ChunkType::from_str("ruSt").unwrap(),
+                vec![i as u8; data_len],
+            )
+        })
+        .collect();
+    chunks.push(Chunk::new(
+        ChunkType::from_str("laSt").unwrap(),
+        vec![0; data_len],
+    ));
+    Png::from_chunks(chunks)
+}
+
+fn label(total_size: usize, chunk_count: usize) -> String {
+    format!("{}MB/{}chunks", total_size / MB, chunk_count)
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    for (size, count) in scenarios() {
+        let bytes = synthetic_png(size, count).as_bytes();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(label(size, count)),
+            &bytes,
+            |b, bytes| b.iter(|| Png::try_from(black_box(&bytes[..])).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn bench_parse_reader(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_reader");
+    group.sample_size(10);
+    for (size, count) in scenarios() {
+        let bytes = synthetic_png(size, count).as_bytes();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(label(size, count)),
+            &bytes,
+            |b, bytes| b.iter(|| Png::from_reader(black_box(&bytes[..])).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn bench_serialize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialize");
+    group.sample_size(10);
+    for (size, count) in scenarios() {
+        let png = synthetic_png(size, count);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(label(size, count)),
+            &png,
+            |b, png| b.iter(|| black_box(png).as_bytes()),
+        );
+    }
+    group.finish();
+}
+
+fn bench_crc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("crc");
+    group.sample_size(10);
+    for (size, count) in scenarios() {
+        let png = synthetic_png(size, count);
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(label(size, count)),
+            &png,
+            |b, png| {
+                b.iter(|| {
+                    black_box(png)
+                        .chunks()
+                        .iter()
+                        .fold(0u32, |acc, chunk| acc ^ chunk.crc())
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("chunk_by_type");
+    for (size, count) in scenarios() {
+        let png = synthetic_png(size, count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(label(size, count)),
+            &png,
+            |b, png| {
+                b.iter(|| {
+                    black_box(png)
+                        .chunk_by_type(black_box("laSt"))
+                        .unwrap()
+                        .length()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+    group.sample_size(10);
+    for (size, count) in scenarios() {
+        let bytes = synthetic_png(size, count).as_bytes();
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(label(size, count)),
+            &bytes,
+            |b, bytes| {
+                b.iter(|| {
+                    scan::png_signatures(black_box(bytes)).count()
+                        + scan::chunk_headers(black_box(bytes), b"laSt").count()
+                        + scan::magic_hits(black_box(bytes)).len()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse,
+    bench_parse_reader,
+    bench_serialize,
+    bench_crc,
+    bench_lookup,
+    bench_scan
+);
+criterion_main!(benches);
@@ -0,0 +1,58 @@
+//! Node.js bindings for pngme, built with napi-rs.
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use pngme::Png;
+
+#[napi(object)]
+pub struct ChunkInfo {
+    pub chunk_type: String,
+    pub length: u32,
+    pub crc: u32,
+}
+
+fn to_napi_error(e: impl std::fmt::Display) -> napi::Error {
+    napi::Error::from_reason(e.to_string())
+}
+
+fn parse_png(png: &[u8]) -> napi::Result<Png> {
+    Png::try_from(png).map_err(to_napi_error)
+}
+
+/// Lists the chunks of a PNG file.
+#[napi]
+pub fn parse(png: Buffer) -> napi::Result<Vec<ChunkInfo>> {
+    let png = parse_png(&png)?;
+    Ok(png
+        .chunks()
+        .iter()
+        .map(|c| ChunkInfo {
+            chunk_type: c.chunk_type().to_string(),
+            length: c.length(),
+            crc: c.crc(),
+        })
+        .collect())
+}
+
+/// Returns a copy of `png` with `message` in a chunk of `chunkType` inserted
+/// before IEND.
+#[napi]
+pub fn encode(png: Buffer, chunk_type: String, message: Buffer) -> napi::Result<Buffer> {
+    let png = pngme::encode_message(&png, &chunk_type, &message).map_err(to_napi_error)?;
+    Ok(png.into())
+}
+
+/// Returns the message stored in the chunks of `chunkType`, decoded as the CLI
+/// decodes it, or null if there is none.
+#[napi]
+pub fn decode(png: Buffer, chunk_type: String) -> napi::Result<Option<Buffer>> {
+    let message = pngme::decode_message(&png, &chunk_type).map_err(to_napi_error)?;
+    Ok(message.map(Buffer::from))
+}
+
+/// Returns a copy of `png` without the first chunk of `chunkType`.
+#[napi]
+pub fn remove(png: Buffer, chunk_type: String) -> napi::Result<Buffer> {
+    let png = pngme::remove_message(&png, &chunk_type).map_err(to_napi_error)?;
+    Ok(png.into())
+}
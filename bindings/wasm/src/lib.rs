@@ -0,0 +1,27 @@
+//! WebAssembly bindings for pngme, built with wasm-bindgen.
+
+use wasm_bindgen::prelude::*;
+
+fn to_js_error(e: impl std::fmt::Display) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// Returns a copy of `png` with `message` in a chunk of `chunkType` inserted
+/// before IEND.
+#[wasm_bindgen(js_name = encodeMessage)]
+pub fn encode_message(png: &[u8], chunk_type: &str, message: &[u8]) -> Result<Vec<u8>, JsError> {
+    pngme::encode_message(png, chunk_type, message).map_err(to_js_error)
+}
+
+/// Returns the message stored in the chunks of `chunkType`, decoded as the
+/// CLI decodes it, or undefined if there is none.
+#[wasm_bindgen(js_name = decodeMessage)]
+pub fn decode_message(png: &[u8], chunk_type: &str) -> Result<Option<Vec<u8>>, JsError> {
+    pngme::decode_message(png, chunk_type).map_err(to_js_error)
+}
+
+/// Returns a copy of `png` without the first chunk of `chunkType`.
+#[wasm_bindgen(js_name = removeMessage)]
+pub fn remove_message(png: &[u8], chunk_type: &str) -> Result<Vec<u8>, JsError> {
+    pngme::remove_message(png, chunk_type).map_err(to_js_error)
+}
@@ -0,0 +1,738 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+use pngme::store::MessageStore;
+
+#[derive(Parser, Debug)]
+#[command(name = "pngme")]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+/// Options accepted by every command.
+#[derive(Args, Debug)]
+pub struct GlobalArgs {
+    /// print sizes in KiB/MiB/GiB instead of bytes
+    #[arg(long, short = 'H', global = true)]
+    pub human_readable: bool,
+
+    /// gzip images written to stdout; gzip-compressed images on stdin are
+    /// detected without it
+    #[arg(long, global = true)]
+    pub gzip: bool,
+
+    /// look chunks up through a FILE.pngidx offset index, written on first use
+    /// and rebuilt whenever FILE changes
+    #[arg(long, global = true)]
+    pub chunk_index: bool,
+
+    /// output format of decode, print, keys, info, scan and validate; json
+    /// prints chunk data as UTF-8 text when valid, base64 otherwise
+    #[arg(long, value_enum, global = true, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Encode a message into a PNG file
+    Encode(EncodeArgs),
+    /// Decode a message stored in a PNG file
+    Decode(DecodeArgs),
+    /// Remove a message from a PNG file
+    Remove(RemoveArgs),
+    /// Print a list of PNG chunks that can be searched for messages
+    Print(PrintArgs),
+    /// List the keys of the messages stored with encode --key
+    Keys(KeysArgs),
+    /// Print the image's dimensions, color type and other header details
+    Info(InfoArgs),
+    /// Print a chunk's data as a hex and ASCII dump with file offsets
+    Dump(DumpArgs),
+    /// List every chunk with its offset and CRC status, flagging non-standard ones
+    #[command(visible_alias = "list")]
+    Scan(ScanArgs),
+    /// Compare the chunks of two PNG files
+    Diff(DiffArgs),
+    /// Render the chunk layout of a file as a diagram
+    Layout(LayoutArgs),
+    /// Print a digest of every chunk and of the whole file
+    Checksum(ChecksumArgs),
+    /// Report how much compressing text and private chunks would save
+    Compress(CompressArgs),
+    /// Check chunk CRCs, lengths and order, and that compressed streams inflate cleanly
+    Validate(ValidateArgs),
+    /// Build or query an on-disk index of the PNG files in a directory
+    Index(IndexArgs),
+    /// Report author, copyright and license metadata of the PNG files in a directory
+    LicenseScan(LicenseScanArgs),
+    /// Find PNG files in a directory whose decoded pixels are identical
+    DedupeImages(DedupeImagesArgs),
+    /// Copy metadata chunks from a master image onto resized or recompressed copies
+    Mirror(MirrorArgs),
+    /// Interactively hide a message in a PNG file, step by step
+    Wizard,
+    /// Remove metadata chunks before publishing an image
+    Strip(StripArgs),
+    /// Hide a message in the least significant bits of the pixels
+    StegoEncode(StegoEncodeArgs),
+    /// Recover a message hidden with stego-encode
+    StegoDecode(StegoDecodeArgs),
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct EncodeArgs {
+    /// input/output file, a directory or glob pattern to encode every PNG
+    /// file it names, or - to read a PNG from stdin and write it to stdout;
+    /// a PPM/PGM/PAM image on stdin is turned into a PNG carrier
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// chunk type; left out with --key, whose message is the only value
+    /// after FILE
+    #[arg(value_name = "CHUNK", required_unless_present = "key")]
+    pub chunk_type: Option<String>,
+
+    /// message to encode, read from stdin when omitted. {env:NAME}, {date},
+    /// {file.stem}, {file.name} and {git.sha} are expanded; use {{ and }}
+    /// for literal braces
+    #[arg(value_name = "MESSAGE")]
+    pub message: Option<String>,
+
+    /// encode MESSAGE as is, without expanding placeholders
+    #[arg(long)]
+    pub no_interpolate: bool,
+
+    /// transport encoding of a message read from stdin
+    #[arg(long, value_enum, default_value_t = StdinFormat::Raw)]
+    pub stdin_format: StdinFormat,
+
+    /// add the message as a new record of an existing chunk instead of a new chunk
+    #[arg(long, conflicts_with = "text")]
+    pub append_record: bool,
+
+    /// store MESSAGE as a standard text chunk under KEYWORD; CHUNK must be
+    /// tEXt, zTXt (compressed) or iTXt (UTF-8)
+    #[arg(long, value_name = "KEYWORD")]
+    pub text: Option<String>,
+
+    /// encrypt the message with a passphrase, prompted for or taken from
+    /// PNGME_PASSPHRASE
+    #[arg(long, conflicts_with = "text")]
+    pub encrypt: bool,
+
+    /// compress the message before embedding it, with deflate unless zstd
+    /// is given; decode detects and decompresses it
+    #[arg(
+        long,
+        value_enum,
+        value_name = "METHOD",
+        num_args = 0..=1,
+        default_missing_value = "deflate",
+        conflicts_with_all = ["text", "append_record"]
+    )]
+    pub compress: Option<CompressMethod>,
+
+    /// sign the message with an HMAC-SHA256 under the key in --key-file, so
+    /// decode --verify can detect tampering
+    #[arg(long, requires = "key_file", conflicts_with_all = ["text", "append_record"])]
+    pub sign: bool,
+
+    /// file holding the secret key for --sign
+    #[arg(long, value_name = "KEYFILE", requires = "sign")]
+    pub key_file: Option<PathBuf>,
+
+    /// language tag of an iTXt chunk, e.g. en or de-CH
+    #[arg(long, value_name = "TAG", requires = "text")]
+    pub language: Option<String>,
+
+    /// insert the chunk at a random valid position between IHDR and IEND
+    #[arg(long, conflicts_with = "position")]
+    pub random_position: bool,
+
+    /// where to insert the chunk instead of before IEND: before-idat,
+    /// before-iend or index=N
+    #[arg(long)]
+    pub position: Option<Position>,
+
+    /// split the message across chunks of CHUNK holding at most BYTES of data
+    /// each; decode joins them again
+    #[arg(
+        long,
+        value_name = "BYTES",
+        value_parser = clap::value_parser!(u32).range(13..=(1 << 31) - 1),
+        conflicts_with_all = ["append_record", "text", "random_position", "position"]
+    )]
+    pub split: Option<u32>,
+
+    /// seed for --random-position, for reproducible output
+    #[arg(long, requires = "random_position")]
+    pub seed: Option<u64>,
+
+    /// store the message under KEY in the message store chunk instead of in
+    /// a chunk of its own, replacing any message already stored under KEY
+    #[arg(
+        long,
+        value_name = "KEY",
+        conflicts_with_all = ["text", "append_record", "split", "random_position", "position"]
+    )]
+    pub key: Option<String>,
+
+    /// also encode PNG files in subdirectories when FILE is a directory
+    #[arg(long)]
+    pub recursive: bool,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[command(flatten)]
+    pub plan: PlanArgs,
+}
+
+impl EncodeArgs {
+    /// CHUNK, or the message store chunk that --key stores the message in.
+    pub fn chunk_type(&self) -> &str {
+        self.chunk_type
+            .as_deref()
+            .unwrap_or(MessageStore::CHUNK_TYPE)
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Position {
+    BeforeIdat,
+    BeforeIend,
+    Index(usize),
+}
+
+impl std::str::FromStr for Position {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "before-idat" => Ok(Position::BeforeIdat),
+            "before-iend" => Ok(Position::BeforeIend),
+            _ => s
+                .strip_prefix("index=")
+                .and_then(|n| n.parse().ok())
+                .map(Position::Index)
+                .ok_or_else(|| "expected before-idat, before-iend or index=N".to_string()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum CompressMethod {
+    Deflate,
+    Zstd,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum StdinFormat {
+    Raw,
+    Base64,
+    Hex,
+}
+
+#[derive(Args, Debug)]
+pub struct DecodeArgs {
+    /// input file, or - for stdin
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// chunk type
+    #[arg(
+        value_name = "CHUNK",
+        required_unless_present = "key",
+        conflicts_with = "key"
+    )]
+    pub chunk_type: Option<String>,
+
+    /// decode the message stored under KEY with encode --key
+    #[arg(
+        long,
+        value_name = "KEY",
+        conflicts_with_all = ["records", "record", "keyword", "all", "index", "match_data"]
+    )]
+    pub key: Option<String>,
+
+    /// list the records stored in the chunk
+    #[arg(long, conflicts_with_all = ["record", "all"])]
+    pub records: bool,
+
+    /// print a single record of the chunk
+    #[arg(long, value_name = "INDEX", conflicts_with = "all")]
+    pub record: Option<usize>,
+
+    /// decode the text chunk of type CHUNK with this keyword
+    #[arg(long, conflicts_with_all = ["records", "record", "all", "index", "match_data"])]
+    pub keyword: Option<String>,
+
+    /// decrypt a message stored with encode --encrypt, prompting for the
+    /// passphrase unless PNGME_PASSPHRASE is set
+    #[arg(long, conflicts_with_all = ["records", "record", "keyword", "all"])]
+    pub decrypt: bool,
+
+    /// check the signature of a message stored with encode --sign and fail
+    /// if it does not match
+    #[arg(long, requires = "key_file", conflicts_with_all = ["keyword", "all"])]
+    pub verify: bool,
+
+    /// file holding the secret key for --verify
+    #[arg(long, value_name = "KEYFILE", requires = "verify")]
+    pub key_file: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub select: SelectArgs,
+}
+
+impl DecodeArgs {
+    /// CHUNK, or the message store chunk that --key reads the message from.
+    pub fn chunk_type(&self) -> &str {
+        self.chunk_type
+            .as_deref()
+            .unwrap_or(MessageStore::CHUNK_TYPE)
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct RemoveArgs {
+    /// input/output file, a directory or glob pattern to remove the chunk
+    /// from every PNG file it names, or - for stdin and stdout
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// chunk type
+    #[arg(
+        value_name = "CHUNK",
+        required_unless_present = "key",
+        conflicts_with = "key"
+    )]
+    pub chunk_type: Option<String>,
+
+    /// remove the message stored under KEY with encode --key
+    #[arg(long, value_name = "KEY", conflicts_with_all = ["all", "index", "match_data"])]
+    pub key: Option<String>,
+
+    #[command(flatten)]
+    pub select: SelectArgs,
+
+    /// also process PNG files in subdirectories when FILE is a directory
+    #[arg(long)]
+    pub recursive: bool,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[command(flatten)]
+    pub plan: PlanArgs,
+}
+
+impl RemoveArgs {
+    /// CHUNK, or the message store chunk that --key removes the message from.
+    pub fn chunk_type(&self) -> &str {
+        self.chunk_type
+            .as_deref()
+            .unwrap_or(MessageStore::CHUNK_TYPE)
+    }
+}
+
+/// Options for previewing what a command changes.
+#[derive(Args, Clone, Debug)]
+pub struct PlanArgs {
+    /// list the chunks that would be inserted, modified or removed and the
+    /// change in file size, without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// log each step, and list the changes before writing them
+    #[arg(long, short)]
+    pub verbose: bool,
+}
+
+impl PlanArgs {
+    /// Whether the changes are listed before they are written.
+    pub fn shows_changes(&self) -> bool {
+        self.dry_run || self.verbose
+    }
+}
+
+/// Which chunks of type CHUNK a command acts on, the first one by default.
+#[derive(Args, Debug)]
+pub struct SelectArgs {
+    /// every chunk of type CHUNK
+    #[arg(long, conflicts_with = "index")]
+    pub all: bool,
+
+    /// the chunk of type CHUNK at INDEX, counting from 0 in file order
+    #[arg(long, value_name = "INDEX")]
+    pub index: Option<usize>,
+
+    /// only chunks whose data contains SUBSTRING; INDEX counts matching
+    /// chunks only
+    #[arg(long, value_name = "SUBSTRING")]
+    pub match_data: Option<String>,
+}
+
+impl SelectArgs {
+    /// Whether any selector was given, as opposed to the first chunk.
+    pub fn is_set(&self) -> bool {
+        self.all || self.index.is_some() || self.match_data.is_some()
+    }
+}
+
+/// Options shared by commands that rewrite the input file.
+#[derive(Args, Clone, Debug)]
+pub struct OutputArgs {
+    /// write the result to FILE instead of modifying the input, - for stdout
+    #[arg(long = "output", value_name = "FILE")]
+    pub path: Option<PathBuf>,
+
+    /// move the file being replaced to the trash before writing the new version
+    #[arg(long)]
+    pub trash: bool,
+
+    /// keep a copy of the file being replaced as FILE.bak
+    #[arg(long)]
+    pub backup: bool,
+
+    /// overwrite the --output file if it already exists
+    #[arg(long)]
+    pub force: bool,
+
+    /// write the PNG as source code instead, to --output or stdout
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub emit: Option<EmitFormat>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum EmitFormat {
+    CArray,
+    RustBytes,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Args, Debug)]
+pub struct PrintArgs {
+    /// input file
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct KeysArgs {
+    /// input file, or - for stdin
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct InfoArgs {
+    /// input file
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct DumpArgs {
+    /// input file, or - for stdin
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// chunk type
+    #[arg(value_name = "CHUNK")]
+    pub chunk_type: String,
+
+    #[command(flatten)]
+    pub select: SelectArgs,
+
+    /// only dump bytes START to END of the data, either of which may be
+    /// left out
+    #[arg(long, value_name = "START..END")]
+    pub range: Option<ByteRange>,
+
+    /// bytes per line
+    #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u16).range(1..))]
+    pub width: u16,
+}
+
+/// A slice of chunk data, `START..END` with END exclusive.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ByteRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+impl std::str::FromStr for ByteRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |n: &str| match n {
+            "" => Ok(None),
+            _ => n
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("invalid offset {}", n)),
+        };
+        let (start, end) = s.split_once("..").ok_or("expected START..END")?;
+        let range = ByteRange {
+            start: parse(start)?.unwrap_or(0),
+            end: parse(end)?,
+        };
+        match range.end {
+            Some(end) if end < range.start => Err("END must not be before START".to_string()),
+            _ => Ok(range),
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct ScanArgs {
+    /// input file
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// original file
+    #[arg(value_name = "OLD")]
+    pub old_path: PathBuf,
+
+    /// changed file
+    #[arg(value_name = "NEW")]
+    pub new_path: PathBuf,
+
+    /// show a hex diff of the data of differing chunks
+    #[arg(long)]
+    pub data: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum LayoutFormat {
+    Svg,
+    Dot,
+}
+
+#[derive(Args, Debug)]
+pub struct LayoutArgs {
+    /// input file
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// diagram format
+    #[arg(long, value_enum, default_value_t = LayoutFormat::Svg)]
+    pub format: LayoutFormat,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum DigestAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+#[derive(Args, Debug)]
+pub struct ChecksumArgs {
+    /// input file
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// digest algorithm
+    #[arg(long, value_enum, default_value_t = DigestAlgorithm::Sha256)]
+    pub algo: DigestAlgorithm,
+
+    /// also print a digest of the decoded pixel data
+    #[arg(long)]
+    pub pixels: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct DedupeImagesArgs {
+    /// directory to search recursively
+    #[arg(value_name = "DIR")]
+    pub dir: PathBuf,
+
+    /// replace duplicates with hard links to the first file of each group
+    /// stored the same way; .png.gz files are only linked to each other
+    #[arg(long)]
+    pub hardlink: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct MirrorArgs {
+    /// image to copy the chunks from
+    #[arg(value_name = "MASTER")]
+    pub master: PathBuf,
+
+    /// derived images to copy the chunks to, modified in place
+    #[arg(value_name = "DERIVED", required = true)]
+    pub derived: Vec<PathBuf>,
+
+    /// chunk type to copy, repeatable; defaults to tEXt, zTXt, iTXt and all
+    /// private chunks
+    #[arg(long = "chunk", value_name = "CHUNK")]
+    pub chunk_types: Vec<String>,
+
+    /// most perceptual hash bits (of 64) a derived image may differ in
+    #[arg(long, default_value_t = 10)]
+    pub max_distance: u32,
+
+    /// copy the chunks even to images that do not look related to the master
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct StegoEncodeArgs {
+    /// input/output file
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// message to hide, read from stdin when omitted
+    #[arg(value_name = "MESSAGE")]
+    pub message: Option<String>,
+
+    /// only print how many bytes FILE can hide
+    #[arg(long, conflicts_with_all = ["message", "encrypt"])]
+    pub capacity: bool,
+
+    /// encrypt the message with a passphrase, prompted for or taken from
+    /// PNGME_PASSPHRASE
+    #[arg(long)]
+    pub encrypt: bool,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct StegoDecodeArgs {
+    /// input file
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// decrypt a message hidden with stego-encode --encrypt
+    #[arg(long)]
+    pub decrypt: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct StripArgs {
+    /// input/output file, a directory or glob pattern to strip every PNG
+    /// file it names, or - for stdin and stdout
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// keep ancillary chunks of this type, repeatable; e.g. tRNS, gAMA and
+    /// iCCP affect how the image looks
+    #[arg(long = "keep", value_name = "CHUNK")]
+    pub keep: Vec<String>,
+
+    /// only remove chunks of this type instead of every ancillary chunk,
+    /// repeatable
+    #[arg(long = "drop", value_name = "CHUNK", conflicts_with = "keep")]
+    pub drop: Vec<String>,
+
+    /// with --drop, also remove every private chunk
+    #[arg(long, requires = "drop")]
+    pub drop_private: bool,
+
+    /// also process PNG files in subdirectories when FILE is a directory
+    #[arg(long)]
+    pub recursive: bool,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct CompressArgs {
+    /// input/output file
+    #[arg(value_name = "FILE")]
+    pub file_path: PathBuf,
+
+    /// convert tEXt chunks to zTXt where that makes the file smaller
+    #[arg(long)]
+    pub apply: bool,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    /// input files; directories are searched recursively for PNG files
+    #[arg(value_name = "FILE", required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// report format
+    #[arg(long, value_enum, default_value_t = ReportFormat::Text, conflicts_with = "format")]
+    pub report: ReportFormat,
+
+    /// rewrite files with bad CRCs, unreadable chunks or trailing data,
+    /// recomputing CRCs and dropping the bytes that cannot be parsed
+    #[arg(long)]
+    pub fix: bool,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    /// JUnit XML with one test case per file
+    Junit,
+}
+
+#[derive(Args, Debug)]
+pub struct IndexArgs {
+    #[command(subcommand)]
+    pub command: IndexCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IndexCommand {
+    /// Index every PNG file below DIR, reusing entries of unchanged files
+    Build(IndexBuildArgs),
+    /// List indexed files matching all of the given criteria
+    Query(IndexQueryArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct IndexBuildArgs {
+    /// directory to index
+    #[arg(value_name = "DIR")]
+    pub dir: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct IndexQueryArgs {
+    /// indexed directory
+    #[arg(value_name = "DIR")]
+    pub dir: PathBuf,
+
+    /// files containing a chunk of this type
+    #[arg(long, value_name = "CHUNK")]
+    pub chunk_type: Option<String>,
+
+    /// files with a text chunk using this keyword
+    #[arg(long)]
+    pub keyword: Option<String>,
+
+    /// files with a chunk payload of this format (zip, pdf, jpeg, ...)
+    #[arg(long)]
+    pub magic: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct LicenseScanArgs {
+    /// directory to search recursively
+    #[arg(value_name = "DIR")]
+    pub dir: PathBuf,
+}
@@ -1,4 +1,12 @@
-use crate::chunk_type::ChunkType;
+use std::borrow::Cow;
+
+use base64::Engine;
+use hmac::{Hmac, KeyInit, Mac};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use sha2::Sha256;
+
+use crate::chunk_type::{ChunkType, ChunkTypeError};
+use crate::compression::{self, CompressionError, Method};
 use crate::Result;
 
 #[derive(Debug, thiserror::Error)]
@@ -7,19 +15,105 @@ pub enum ChunkError {
     LengthMismatch,
     #[error("CRC mismatch")]
     CrcMismatch,
+    #[error("chunk is truncated")]
+    Truncated,
+    #[error("chunk length {0} exceeds the 2^31-1 limit")]
+    TooLong(u32),
+    #[error("chunk data of {0} bytes exceeds the 2^31-1 limit")]
+    DataTooLong(usize),
+    #[error("invalid chunk type: {0}")]
+    InvalidType(#[from] ChunkTypeError),
+}
+
+/// Why the signature of a chunk's data could not be checked, or did not
+/// match.
+#[derive(Debug, thiserror::Error)]
+pub enum SignatureError {
+    #[error("chunk data is not signed")]
+    NotSigned,
+    #[error("unsupported signature version {0}")]
+    UnsupportedVersion(u8),
+    #[error("unknown signature algorithm {0}")]
+    UnknownAlgorithm(u8),
+    #[error("signed chunk data is truncated")]
+    Truncated,
+    #[error("signature does not match: the data was tampered with or the key is wrong")]
+    Mismatch,
+}
+
+/// How the data of a signed chunk is authenticated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    /// HMAC-SHA256 under a shared secret key.
+    HmacSha256,
+}
+
+impl SignatureAlgorithm {
+    fn from_marker(marker: u8) -> Option<SignatureAlgorithm> {
+        match marker {
+            0 => Some(SignatureAlgorithm::HmacSha256),
+            _ => None,
+        }
+    }
+
+    fn marker(self) -> u8 {
+        match self {
+            SignatureAlgorithm::HmacSha256 => 0,
+        }
+    }
+
+    fn signature_len(self) -> usize {
+        match self {
+            SignatureAlgorithm::HmacSha256 => 32,
+        }
+    }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Chunk {
     chunk_type: ChunkType,
     data: Vec<u8>,
 }
 
 impl Chunk {
+    /// Largest data length the PNG specification allows.
+    pub const MAX_LENGTH: usize = (1 << 31) - 1;
+
+    /// # Panics
+    ///
+    /// If `data` is longer than [`Chunk::MAX_LENGTH`]; use [`Chunk::try_new`]
+    /// for data of unchecked size.
     pub fn new(chunk_type: ChunkType, data: Vec<u8>) -> Chunk {
-        Chunk { chunk_type, data }
+        Chunk::try_new(chunk_type, data).expect("chunk data exceeds Chunk::MAX_LENGTH")
+    }
+
+    /// A chunk holding `data`, or an error if it is longer than
+    /// [`Chunk::MAX_LENGTH`].
+    pub fn try_new(chunk_type: ChunkType, data: Vec<u8>) -> std::result::Result<Chunk, ChunkError> {
+        Chunk::check_length(data.len())?;
+        Ok(Chunk { chunk_type, data })
+    }
+
+    fn check_length(len: usize) -> std::result::Result<(), ChunkError> {
+        match len > Chunk::MAX_LENGTH {
+            true => Err(ChunkError::DataTooLong(len)),
+            false => Ok(()),
+        }
+    }
+
+    /// A chunk holding `data` compressed with `method`, behind a header that
+    /// [`Chunk::decompressed_data`] recognises.
+    pub fn new_compressed(chunk_type: ChunkType, data: &[u8], method: Method) -> Chunk {
+        Chunk::new(chunk_type, compression::compress(data, method))
+    }
+
+    /// A chunk holding `payload` signed with `key`; see [`sign`].
+    pub fn new_signed(chunk_type: ChunkType, payload: &[u8], key: &[u8]) -> Chunk {
+        Chunk::new(chunk_type, sign(payload, key))
     }
 
     pub fn length(&self) -> u32 {
+        // Every constructor keeps the data within MAX_LENGTH.
         self.data.len() as u32
     }
 
@@ -31,8 +125,25 @@ impl Chunk {
         &self.data
     }
 
+    /// The data of a chunk made by [`Chunk::new_compressed`], decompressed.
+    /// Data that is not compressed is returned as is.
+    pub fn decompressed_data(&self) -> std::result::Result<Cow<'_, [u8]>, CompressionError> {
+        match compression::decompress(&self.data) {
+            Some(data) => Ok(Cow::Owned(data?)),
+            None => Ok(Cow::Borrowed(&self.data)),
+        }
+    }
+
+    /// Replaces the data, failing like [`Chunk::try_new`] if it is too long.
+    pub fn set_data(&mut self, data: Vec<u8>) -> std::result::Result<(), ChunkError> {
+        Chunk::check_length(data.len())?;
+        self.data = data;
+        Ok(())
+    }
+
     pub fn crc(&self) -> u32 {
-        crc::crc32::checksum_ieee(&self.type_and_data_bytes())
+        let crc = crc::crc32::update(0, &crc::crc32::IEEE_TABLE, &self.chunk_type.bytes());
+        crc::crc32::update(crc, &crc::crc32::IEEE_TABLE, &self.data)
     }
 
     pub fn data_as_string(&self) -> Result<String> {
@@ -44,56 +155,107 @@ impl Chunk {
         }
     }
 
+    /// The data as a hex and ASCII dump of `width` bytes per line; see
+    /// [`hexdump`].
+    pub fn hexdump(&self, width: usize) -> String {
+        hexdump(&self.data, width, 0)
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
-        let mut bytes = self.length().to_be_bytes().to_vec();
-        bytes.extend(self.chunk_type.bytes());
-        bytes.extend(self.data.to_vec());
-        bytes.extend(self.crc().to_be_bytes().to_vec());
+        let mut bytes = Vec::with_capacity(self.data.len() + 12);
+        self.write_to(&mut bytes).unwrap();
         bytes
     }
 
-    fn type_and_data_bytes(&self) -> Vec<u8> {
-        let mut bytes = self.chunk_type.bytes().to_vec();
-        bytes.extend(self.data.to_vec());
-        bytes
+    /// Writes the chunk as it appears in a file, without copying the data.
+    pub fn write_to(&self, mut writer: impl std::io::Write) -> std::io::Result<()> {
+        writer.write_all(&self.length().to_be_bytes())?;
+        writer.write_all(&self.chunk_type.bytes())?;
+        writer.write_all(&self.data)?;
+        writer.write_all(&self.crc().to_be_bytes())
+    }
+}
+
+/// A canonical hex and ASCII dump of `bytes`, `width` bytes per line, each
+/// line starting with its offset counted from `offset`. Bytes outside
+/// printable ASCII show as `.` in the ASCII column.
+pub fn hexdump(bytes: &[u8], width: usize, offset: u64) -> String {
+    let width = width.max(1);
+    let mut out = String::new();
+    for (idx, line) in bytes.chunks(width).enumerate() {
+        let mut hex = String::new();
+        for col in 0..width {
+            if col > 0 && col % 8 == 0 {
+                hex.push(' ');
+            }
+            match line.get(col) {
+                Some(byte) => hex.push_str(&format!("{:02x} ", byte)),
+                None => hex.push_str("   "),
+            }
+        }
+        let ascii: String = line
+            .iter()
+            .map(|&b| match b.is_ascii_graphic() || b == b' ' {
+                true => b as char,
+                false => '.',
+            })
+            .collect();
+        out.push_str(&format!(
+            "{:08x}  {} |{}|\n",
+            offset + (idx * width) as u64,
+            hex,
+            ascii
+        ));
     }
+    out
 }
 
 impl TryFrom<&Vec<u8>> for Chunk {
     type Error = ChunkError;
 
     fn try_from(value: &Vec<u8>) -> std::result::Result<Chunk, Self::Error> {
-        // len: 4 bytes
-        // chunk type: 4 bytes
-        // data: data_len bytes
-        // crc: 4 bytes
-
-        let data_len_bytes: [u8; 4] = value[..4].try_into().unwrap();
-        let data_len = u32::from_be_bytes(data_len_bytes) as usize;
-
-        let total_len = value.len();
-        if total_len != 4 + 4 + data_len + 4 {
-            return Err(ChunkError::LengthMismatch);
-        }
+        parse(value)
+    }
+}
 
-        let chunk_type_bytes: [u8; 4] = value[4..8].try_into().unwrap();
-        let chunk_type = ChunkType::try_from(chunk_type_bytes).unwrap();
+/// Parses exactly one chunk: length, type, data and CRC.
+pub(crate) fn parse(bytes: &[u8]) -> std::result::Result<Chunk, ChunkError> {
+    let (data_len, chunk_type, rest) = split_header(bytes)?;
+    if rest.len() < data_len + 4 {
+        return Err(ChunkError::Truncated);
+    }
+    if rest.len() != data_len + 4 {
+        return Err(ChunkError::LengthMismatch);
+    }
 
-        let data_bytes = &value[8..(total_len - 4)];
+    let (data_bytes, crc_bytes) = rest.split_at(data_len);
+    let parsed_crc = u32::from_be_bytes(crc_bytes.try_into().unwrap());
 
-        let crc_bytes: [u8; 4] = value[(total_len - 4)..].try_into().unwrap();
-        let parsed_crc = u32::from_be_bytes(crc_bytes);
+    let chunk = Chunk {
+        chunk_type,
+        data: data_bytes.to_vec(),
+    };
+    if chunk.crc() != parsed_crc {
+        return Err(ChunkError::CrcMismatch);
+    }
 
-        let chunk = Chunk {
-            chunk_type,
-            data: data_bytes.to_vec(),
-        };
-        if chunk.crc() != parsed_crc {
-            return Err(ChunkError::CrcMismatch);
-        }
+    Ok(chunk)
+}
 
-        Ok(chunk)
+/// Splits the length and type off the start of a chunk, checking the length
+/// against [`Chunk::MAX_LENGTH`].
+pub(crate) fn split_header(
+    bytes: &[u8],
+) -> std::result::Result<(usize, ChunkType, &[u8]), ChunkError> {
+    let (length, rest) = bytes
+        .split_first_chunk::<4>()
+        .ok_or(ChunkError::Truncated)?;
+    let (type_bytes, rest) = rest.split_first_chunk::<4>().ok_or(ChunkError::Truncated)?;
+    let length = u32::from_be_bytes(*length);
+    if length as usize > Chunk::MAX_LENGTH {
+        return Err(ChunkError::TooLong(length));
     }
+    Ok((length as usize, ChunkType::try_from(*type_bytes)?, rest))
 }
 
 impl std::fmt::Display for Chunk {
@@ -108,6 +270,128 @@ impl std::fmt::Display for Chunk {
     }
 }
 
+/// Serialized as type, length, CRC and data, see [`encode_data`].
+impl Serialize for Chunk {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let (encoding, data) = encode_data(&self.data);
+        let mut chunk = serializer.serialize_struct("Chunk", 5)?;
+        chunk.serialize_field("type", &self.chunk_type)?;
+        chunk.serialize_field("length", &self.length())?;
+        chunk.serialize_field("crc", &self.crc())?;
+        chunk.serialize_field("encoding", encoding)?;
+        chunk.serialize_field("data", &data)?;
+        chunk.end()
+    }
+}
+
+const SIGNATURE_MAGIC: &[u8; 4] = b"PNGS";
+const SIGNATURE_VERSION: u8 = 1;
+/// Algorithm marker of an unsigned payload escaped by [`escape_unsigned`].
+const UNSIGNED: u8 = 0xff;
+
+/// Prefixes `payload` with its HMAC-SHA256 under `key`. Signed data starts
+/// with a header, so signed and unsigned chunks can sit side by side:
+///
+/// ```text
+/// "PNGS" | version (1) | algorithm (1) | signature | payload
+/// ```
+pub fn sign(payload: &[u8], key: &[u8]) -> Vec<u8> {
+    let algorithm = SignatureAlgorithm::HmacSha256;
+    let mut data = SIGNATURE_MAGIC.to_vec();
+    data.extend([SIGNATURE_VERSION, algorithm.marker()]);
+    data.extend(hmac_sha256(key, payload));
+    data.extend(payload);
+    data
+}
+
+/// `payload` as it should be embedded when it is not signed: unchanged,
+/// unless it starts with the signature magic and could be mistaken for
+/// signed data. [`unsigned_payload`] returns such data as it was.
+pub fn escape_unsigned(payload: &[u8]) -> Cow<'_, [u8]> {
+    if !payload.starts_with(SIGNATURE_MAGIC) {
+        return Cow::Borrowed(payload);
+    }
+    let mut data = SIGNATURE_MAGIC.to_vec();
+    data.extend([SIGNATURE_VERSION, UNSIGNED]);
+    data.extend(payload);
+    Cow::Owned(data)
+}
+
+/// Unsigned `data` without the header [`escape_unsigned`] may have added.
+pub fn unsigned_payload(data: &[u8]) -> &[u8] {
+    data.strip_prefix(SIGNATURE_MAGIC)
+        .and_then(|rest| rest.strip_prefix(&[SIGNATURE_VERSION, UNSIGNED]))
+        .unwrap_or(data)
+}
+
+/// Whether `data` starts with a well-formed header written by [`sign`].
+/// Unsigned messages that merely start with "PNGS" are not signed.
+pub fn is_signed(data: &[u8]) -> bool {
+    split_signed(data).is_ok()
+}
+
+/// Splits signed `data` into its algorithm, signature and payload, without
+/// checking the signature.
+pub fn split_signed(
+    data: &[u8],
+) -> std::result::Result<(SignatureAlgorithm, &[u8], &[u8]), SignatureError> {
+    let rest = data
+        .strip_prefix(SIGNATURE_MAGIC)
+        .ok_or(SignatureError::NotSigned)?;
+    let (&[version, marker], rest) = rest.split_first_chunk().ok_or(SignatureError::Truncated)?;
+    if version != SIGNATURE_VERSION {
+        return Err(SignatureError::UnsupportedVersion(version));
+    }
+    if marker == UNSIGNED {
+        return Err(SignatureError::NotSigned);
+    }
+    let algorithm =
+        SignatureAlgorithm::from_marker(marker).ok_or(SignatureError::UnknownAlgorithm(marker))?;
+    if rest.len() < algorithm.signature_len() {
+        return Err(SignatureError::Truncated);
+    }
+    let (signature, payload) = rest.split_at(algorithm.signature_len());
+    Ok((algorithm, signature, payload))
+}
+
+/// The payload of data produced by [`sign`], once its signature checks out
+/// under `key`.
+pub fn verify<'a>(data: &'a [u8], key: &[u8]) -> std::result::Result<&'a [u8], SignatureError> {
+    let (algorithm, signature, payload) = split_signed(data)?;
+    let valid = match algorithm {
+        SignatureAlgorithm::HmacSha256 => {
+            let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+            mac.update(payload);
+            mac.verify_slice(signature).is_ok()
+        }
+    };
+    match valid {
+        true => Ok(payload),
+        false => Err(SignatureError::Mismatch),
+    }
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac_sha256(key: &[u8], payload: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// `data` as text for machine-readable output: as is when it is valid UTF-8,
+/// base64-encoded otherwise. Returns the encoding ("utf8" or "base64") and
+/// the text.
+pub fn encode_data(data: &[u8]) -> (&'static str, String) {
+    match std::str::from_utf8(data) {
+        Ok(text) => ("utf8", text.to_string()),
+        Err(_) => (
+            "base64",
+            base64::engine::general_purpose::STANDARD.encode(data),
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -132,6 +416,22 @@ mod tests {
         Chunk::try_from(chunk_data.as_ref()).unwrap()
     }
 
+    #[test]
+    fn test_data_too_long() {
+        assert!(Chunk::check_length(Chunk::MAX_LENGTH).is_ok());
+        assert!(matches!(
+            Chunk::check_length(Chunk::MAX_LENGTH + 1),
+            Err(ChunkError::DataTooLong(_))
+        ));
+
+        let mut forged = testing_chunk().as_bytes();
+        forged[..4].copy_from_slice(&(Chunk::MAX_LENGTH as u32 + 1).to_be_bytes());
+        assert!(matches!(
+            Chunk::try_from(&forged),
+            Err(ChunkError::TooLong(_))
+        ));
+    }
+
     #[test]
     fn test_new_chunk() {
         let chunk_type = ChunkType::from_str("RuSt").unwrap();
@@ -217,6 +517,135 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_malformed_chunk_from_bytes() {
+        let bytes = testing_chunk().as_bytes();
+        for len in 0..bytes.len() {
+            assert!(matches!(
+                Chunk::try_from(&bytes[..len].to_vec()),
+                Err(ChunkError::Truncated)
+            ));
+        }
+
+        let mut too_long = bytes.clone();
+        too_long[..4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            Chunk::try_from(&too_long),
+            Err(ChunkError::TooLong(u32::MAX))
+        ));
+
+        let mut bad_type = bytes;
+        bad_type[4] = b'1';
+        assert!(matches!(
+            Chunk::try_from(&bad_type),
+            Err(ChunkError::InvalidType(_))
+        ));
+    }
+
+    #[test]
+    fn test_hexdump() {
+        let chunk = Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            b"A secret\x00\x01message!".to_vec(),
+        );
+        assert_eq!(
+            chunk.hexdump(16),
+            "00000000  41 20 73 65 63 72 65 74  00 01 6d 65 73 73 61 67  |A secret..messag|\n\
+             00000010  65 21                                             |e!|\n"
+        );
+        assert_eq!(hexdump(b"abc", 4, 0x20), "00000020  61 62 63     |abc|\n");
+        assert_eq!(hexdump(b"", 16, 0), "");
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_compressed_data() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let text = "A secret message! ".repeat(50);
+        let chunk = Chunk::new_compressed(chunk_type, text.as_bytes(), Method::Zstd);
+        assert!(chunk.data().len() < text.len());
+        assert_eq!(chunk.decompressed_data().unwrap(), text.as_bytes());
+
+        let plain = Chunk::new(chunk_type, b"plain".to_vec());
+        assert!(matches!(
+            plain.decompressed_data(),
+            Ok(Cow::Borrowed(b"plain"))
+        ));
+    }
+
+    #[test]
+    fn test_signed_data() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let chunk = Chunk::new_signed(chunk_type, b"A secret message!", b"key");
+        assert!(is_signed(chunk.data()));
+        assert!(!is_signed(b"PNGS..hello"));
+        assert_eq!(verify(chunk.data(), b"key").unwrap(), b"A secret message!");
+        assert!(matches!(
+            verify(chunk.data(), b"other key"),
+            Err(SignatureError::Mismatch)
+        ));
+
+        let mut tampered = chunk.data().to_vec();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            verify(&tampered, b"key"),
+            Err(SignatureError::Mismatch)
+        ));
+    }
+
+    #[test]
+    fn test_unsigned_or_malformed_data() {
+        assert!(matches!(
+            verify(b"A secret message!", b"key"),
+            Err(SignatureError::NotSigned)
+        ));
+        assert!(matches!(
+            verify(b"PNGS\x01\x00short", b"key"),
+            Err(SignatureError::Truncated)
+        ));
+        assert!(matches!(
+            verify(b"PNGS\x01\x09", b"key"),
+            Err(SignatureError::UnknownAlgorithm(9))
+        ));
+        assert!(matches!(
+            verify(b"PNGS\x02\x00", b"key"),
+            Err(SignatureError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn test_escaped_unsigned_data() {
+        let mut payload = b"PNGS\x01\x00".to_vec();
+        payload.extend([7; 32]);
+        payload.extend(b"TAIL");
+        let escaped = escape_unsigned(&payload);
+        assert!(!is_signed(&escaped));
+        assert!(matches!(
+            verify(&escaped, b"key"),
+            Err(SignatureError::NotSigned)
+        ));
+        assert_eq!(unsigned_payload(&escaped), payload);
+
+        assert!(matches!(escape_unsigned(b"plain"), Cow::Borrowed(b"plain")));
+        assert_eq!(unsigned_payload(b"PNGS..hello"), b"PNGS..hello");
+    }
+
+    #[test]
+    fn test_serialize() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hi".to_vec());
+        let json = serde_json::to_value(&chunk).unwrap();
+        assert_eq!(json["type"], "ruSt");
+        assert_eq!(json["length"], 2);
+        assert_eq!(json["crc"], chunk.crc());
+        assert_eq!(json["encoding"], "utf8");
+        assert_eq!(json["data"], "hi");
+
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0xff, 0]);
+        let json = serde_json::to_value(&chunk).unwrap();
+        assert_eq!(json["encoding"], "base64");
+        assert_eq!(json["data"], "/wA=");
+    }
+
     #[test]
     pub fn test_chunk_trait_impls() {
         let data_length: u32 = 42;
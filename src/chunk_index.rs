@@ -0,0 +1,123 @@
+//! Chunk offset index for files with very many chunks. The index maps each
+//! chunk type to the offsets of its chunks and records a hash of the file it
+//! was built from, so it can be stored next to the file and reused until the
+//! file changes.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::Chunk;
+use crate::png::{Png, PngError};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ChunkIndex {
+    /// BLAKE3 hash of the indexed file, hex-encoded.
+    hash: String,
+    /// Offsets of the length field of every chunk, by chunk type.
+    offsets: BTreeMap<String, Vec<u64>>,
+}
+
+impl ChunkIndex {
+    /// Walks the chunk headers of `bytes`, without reading chunk data or
+    /// checking CRCs.
+    pub fn build(bytes: &[u8]) -> std::result::Result<ChunkIndex, PngError> {
+        if !bytes.starts_with(&Png::STANDARD_HEADER) {
+            return Err(PngError::WrongHeader);
+        }
+
+        let mut offsets: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+        let mut idx = Png::STANDARD_HEADER.len();
+        while idx < bytes.len() {
+            let header = bytes.get(idx..idx + 8).ok_or(PngError::Corrupted)?;
+            let len = u32::from_be_bytes(header[..4].try_into().unwrap()) as usize;
+            if idx + 4 + 4 + len + 4 > bytes.len() {
+                return Err(PngError::Corrupted);
+            }
+            let chunk_type = String::from_utf8_lossy(&header[4..]).to_string();
+            offsets.entry(chunk_type).or_default().push(idx as u64);
+            idx += 4 + 4 + len + 4;
+        }
+
+        Ok(ChunkIndex {
+            hash: hash(bytes),
+            offsets,
+        })
+    }
+
+    /// Whether the index was built from `bytes`.
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        self.hash == hash(bytes)
+    }
+
+    /// The first chunk of `chunk_type` in `bytes`, read directly from its
+    /// indexed offset. `bytes` must be the indexed file.
+    pub fn chunk(
+        &self,
+        bytes: &[u8],
+        chunk_type: &str,
+    ) -> Option<std::result::Result<Chunk, PngError>> {
+        let start = *self.offsets.get(chunk_type)?.first()? as usize;
+        let read = || {
+            let len_bytes = bytes.get(start..start + 4).ok_or(PngError::Corrupted)?;
+            let len = u32::from_be_bytes(len_bytes.try_into().unwrap()) as usize;
+            let chunk_bytes = bytes
+                .get(start..start + 4 + 4 + len + 4)
+                .ok_or(PngError::Corrupted)?;
+            Chunk::try_from(&chunk_bytes.to_vec()).map_err(|_| PngError::Corrupted)
+        };
+        Some(read())
+    }
+}
+
+fn hash(bytes: &[u8]) -> String {
+    blake3::hash(bytes).to_hex().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn testing_png() -> Vec<u8> {
+        let chunk = |chunk_type: &str, data: &str| {
+            Chunk::new(
+                ChunkType::from_str(chunk_type).unwrap(),
+                data.as_bytes().to_vec(),
+            )
+        };
+        Png::from_chunks(vec![
+            chunk("FrSt", "first"),
+            chunk("ruSt", "message"),
+            chunk("FrSt", "again"),
+        ])
+        .as_bytes()
+    }
+
+    #[test]
+    fn test_chunk_at_indexed_offset() {
+        let bytes = testing_png();
+        let index = ChunkIndex::build(&bytes).unwrap();
+        let chunk = index.chunk(&bytes, "ruSt").unwrap().unwrap();
+        assert_eq!(chunk.data(), b"message");
+        let chunk = index.chunk(&bytes, "FrSt").unwrap().unwrap();
+        assert_eq!(chunk.data(), b"first");
+        assert!(index.chunk(&bytes, "miSs").is_none());
+    }
+
+    #[test]
+    fn test_index_of_changed_file_does_not_match() {
+        let mut bytes = testing_png();
+        let index = ChunkIndex::build(&bytes).unwrap();
+        assert!(index.matches(&bytes));
+        bytes.push(0);
+        assert!(!index.matches(&bytes));
+    }
+
+    #[test]
+    fn test_build_truncated() {
+        let bytes = testing_png();
+        assert!(ChunkIndex::build(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
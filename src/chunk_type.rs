@@ -5,49 +5,130 @@ use thiserror::Error;
 pub enum ChunkTypeError {
     #[error("bytes must be ASCII letters")]
     ExpectAsciiBytes,
+    #[error("chunk type must be 4 bytes long, got {0}")]
+    WrongLength(usize),
+    #[error("{0} is not a private chunk type, its second letter must be lowercase")]
+    NotPrivate(ChunkType),
+    #[error("{0} has the reserved bit set, its third letter must be uppercase")]
+    ReservedBitSet(ChunkType),
 }
 
-#[derive(Debug, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ChunkType {
     bytes: [u8; 4],
 }
 
 impl ChunkType {
-    fn is_critical(&self) -> bool {
-        u8::is_ascii_uppercase(self.bytes().get(0).unwrap())
+    pub const IHDR: ChunkType = ChunkType { bytes: *b"IHDR" };
+    pub const PLTE: ChunkType = ChunkType { bytes: *b"PLTE" };
+    pub const IDAT: ChunkType = ChunkType { bytes: *b"IDAT" };
+    pub const IEND: ChunkType = ChunkType { bytes: *b"IEND" };
+    pub const TRNS: ChunkType = ChunkType { bytes: *b"tRNS" };
+    pub const CHRM: ChunkType = ChunkType { bytes: *b"cHRM" };
+    pub const GAMA: ChunkType = ChunkType { bytes: *b"gAMA" };
+    pub const ICCP: ChunkType = ChunkType { bytes: *b"iCCP" };
+    pub const SBIT: ChunkType = ChunkType { bytes: *b"sBIT" };
+    pub const SRGB: ChunkType = ChunkType { bytes: *b"sRGB" };
+    pub const TEXT: ChunkType = ChunkType { bytes: *b"tEXt" };
+    pub const ZTXT: ChunkType = ChunkType { bytes: *b"zTXt" };
+    pub const ITXT: ChunkType = ChunkType { bytes: *b"iTXt" };
+    pub const BKGD: ChunkType = ChunkType { bytes: *b"bKGD" };
+    pub const HIST: ChunkType = ChunkType { bytes: *b"hIST" };
+    pub const PHYS: ChunkType = ChunkType { bytes: *b"pHYs" };
+    pub const SPLT: ChunkType = ChunkType { bytes: *b"sPLT" };
+    pub const EXIF: ChunkType = ChunkType { bytes: *b"eXIf" };
+    pub const TIME: ChunkType = ChunkType { bytes: *b"tIME" };
+
+    /// A private chunk type, such as `ruSt` for an application's own data.
+    /// Fails unless `bytes` are letters with the private bit set and the
+    /// reserved bit clear.
+    pub fn new_private(bytes: &[u8; 4]) -> std::result::Result<ChunkType, ChunkTypeError> {
+        let chunk_type = ChunkType::try_from(*bytes)?;
+        if chunk_type.is_public() {
+            return Err(ChunkTypeError::NotPrivate(chunk_type));
+        }
+        if !chunk_type.is_reserved_bit_valid() {
+            return Err(ChunkTypeError::ReservedBitSet(chunk_type));
+        }
+        Ok(chunk_type)
+    }
+
+    pub fn is_critical(&self) -> bool {
+        u8::is_ascii_uppercase(self.bytes().first().unwrap())
     }
 
-    fn is_public(&self) -> bool {
+    pub fn is_public(&self) -> bool {
         u8::is_ascii_uppercase(self.bytes().get(1).unwrap())
     }
 
-    fn is_reserved_bit_valid(&self) -> bool {
+    pub fn is_reserved_bit_valid(&self) -> bool {
         u8::is_ascii_uppercase(self.bytes().get(2).unwrap())
     }
 
-    fn is_safe_to_copy(&self) -> bool {
+    pub fn is_safe_to_copy(&self) -> bool {
         u8::is_ascii_lowercase(self.bytes().get(3).unwrap())
     }
 
-    fn is_valid(&self) -> bool {
+    pub fn is_valid(&self) -> bool {
         self.is_reserved_bit_valid()
     }
 
-    pub fn to_string(&self) -> String {
-        return String::from_utf8(self.bytes().try_into().unwrap()).unwrap();
-    }
-
     pub fn bytes(&self) -> [u8; 4] {
         self.bytes
     }
-}
 
-impl PartialEq for ChunkType {
-    fn eq(&self, other: &Self) -> bool {
-        self.bytes() == other.bytes()
+    /// Whether the type is defined by the PNG specification or registered as
+    /// an extension.
+    pub fn is_standard(&self) -> bool {
+        self.known_description().is_some()
+    }
+
+    /// What a chunk of this type holds, for standard and registered types.
+    pub fn known_description(&self) -> Option<&'static str> {
+        KNOWN_TYPES
+            .iter()
+            .find(|(bytes, _)| *bytes == &self.bytes)
+            .map(|(_, description)| *description)
     }
 }
 
+/// Chunk types of the PNG specification (including APNG) and the registered
+/// public extensions.
+const KNOWN_TYPES: [(&[u8; 4], &str); 32] = [
+    (b"IHDR", "image header"),
+    (b"PLTE", "palette"),
+    (b"IDAT", "image data"),
+    (b"IEND", "image trailer"),
+    (b"tRNS", "transparency"),
+    (b"cHRM", "primary chromaticities and white point"),
+    (b"gAMA", "image gamma"),
+    (b"iCCP", "embedded ICC profile"),
+    (b"sBIT", "significant bits"),
+    (b"sRGB", "standard RGB colour space"),
+    (b"cICP", "coding-independent code points"),
+    (b"mDCV", "mastering display colour volume"),
+    (b"cLLI", "content light level"),
+    (b"tEXt", "textual data"),
+    (b"zTXt", "compressed textual data"),
+    (b"iTXt", "international textual data"),
+    (b"bKGD", "background colour"),
+    (b"hIST", "image histogram"),
+    (b"pHYs", "physical pixel dimensions"),
+    (b"sPLT", "suggested palette"),
+    (b"eXIf", "Exif metadata"),
+    (b"tIME", "last modification time"),
+    (b"acTL", "animation control"),
+    (b"fcTL", "frame control"),
+    (b"fdAT", "frame data"),
+    (b"oFFs", "image offset"),
+    (b"pCAL", "pixel value calibration"),
+    (b"sCAL", "physical scale"),
+    (b"sTER", "stereo image indicator"),
+    (b"gIFg", "GIF graphic control extension"),
+    (b"gIFx", "GIF application extension"),
+    (b"dSIG", "digital signature"),
+];
+
 impl TryFrom<[u8; 4]> for ChunkType {
     type Error = ChunkTypeError;
 
@@ -65,18 +146,24 @@ impl FromStr for ChunkType {
     type Err = ChunkTypeError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let x: [u8; 4] = s.as_bytes().try_into().unwrap();
+        let x: [u8; 4] = s
+            .as_bytes()
+            .try_into()
+            .map_err(|_| ChunkTypeError::WrongLength(s.len()))?;
         Self::try_from(x)
     }
 }
 
 impl std::fmt::Display for ChunkType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            String::from_utf8(self.bytes().try_into().unwrap()).unwrap()
-        )
+        write!(f, "{}", String::from_utf8_lossy(&self.bytes))
+    }
+}
+
+/// Serialized as its four-letter name.
+impl serde::Serialize for ChunkType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
     }
 }
 
@@ -101,6 +188,14 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    pub fn test_chunk_type_is_standard() {
+        let chunk = ChunkType::from_str("tEXt").unwrap();
+        assert!(chunk.is_standard());
+        assert_eq!(chunk.known_description(), Some("textual data"));
+        assert!(!ChunkType::from_str("ruSt").unwrap().is_standard());
+    }
+
     #[test]
     pub fn test_chunk_type_is_critical() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
@@ -164,6 +259,43 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    pub fn test_chunk_type_wrong_length() {
+        assert!(matches!(
+            ChunkType::from_str("Rust!"),
+            Err(ChunkTypeError::WrongLength(5))
+        ));
+        assert!(ChunkType::from_str("").is_err());
+    }
+
+    #[test]
+    pub fn test_chunk_type_constants() {
+        assert_eq!(ChunkType::IHDR, ChunkType::from_str("IHDR").unwrap());
+        assert_eq!(ChunkType::TEXT.to_string(), "tEXt");
+        assert!(ChunkType::IEND.is_critical());
+        assert!(ChunkType::PHYS.is_standard());
+        assert!(ChunkType::IDAT < ChunkType::IEND);
+    }
+
+    #[test]
+    pub fn test_new_private() {
+        let chunk = ChunkType::new_private(b"ruSt").unwrap();
+        assert!(!chunk.is_public());
+        assert!(!chunk.is_standard());
+        assert!(matches!(
+            ChunkType::new_private(b"tEXt"),
+            Err(ChunkTypeError::NotPrivate(_))
+        ));
+        assert!(matches!(
+            ChunkType::new_private(b"rust"),
+            Err(ChunkTypeError::ReservedBitSet(_))
+        ));
+        assert!(matches!(
+            ChunkType::new_private(b"ru5t"),
+            Err(ChunkTypeError::ExpectAsciiBytes)
+        ));
+    }
+
     #[test]
     pub fn test_chunk_type_string() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
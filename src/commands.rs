@@ -0,0 +1,892 @@
+use std::{
+    borrow::Cow,
+    io::{IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use base64::Engine;
+use pngme::{
+    chunk::{self, encode_data},
+    chunk_index::ChunkIndex,
+    compression::Method,
+    diff::Change,
+    emit, layout,
+    message::{self, DecodeOptions, EncodeOptions},
+    netpbm,
+    records::Records,
+    report::PngReport,
+    store::MessageStore,
+    text::{TextChunk, TextError, TextKind},
+    Chunk, ChunkType, Png, PngError, PngMeError, Result,
+};
+use rand::{rngs::StdRng, RngExt, SeedableRng};
+use serde::Serialize;
+
+use crate::args::{
+    CompressMethod, DecodeArgs, EmitFormat, EncodeArgs, GlobalArgs, LayoutArgs, LayoutFormat,
+    OutputArgs, OutputFormat, PlanArgs, Position, PrintArgs, RemoveArgs, SelectArgs, StdinFormat,
+};
+use crate::storage;
+use batch::Outcome;
+
+mod batch;
+mod checksum;
+mod compress;
+mod dedupe;
+mod diff;
+mod dump;
+mod index;
+mod info;
+mod license;
+mod mirror;
+mod placeholders;
+mod scan;
+mod stego;
+mod store;
+mod strip;
+mod validate;
+mod wizard;
+
+pub use checksum::checksum;
+pub use compress::compress;
+pub use dedupe::dedupe_images;
+pub use diff::diff;
+pub use dump::dump;
+pub use index::index;
+pub use info::info;
+pub use license::license_scan;
+pub use mirror::mirror;
+pub use scan::scan;
+pub use stego::{stego_decode, stego_encode};
+pub use store::keys;
+pub use strip::strip;
+pub use validate::validate;
+pub use wizard::wizard;
+
+/// Longest text [`display_chunk`] shows in full.
+const DISPLAY_TEXT_CHARS: usize = 1024;
+
+pub fn encode(mut args: EncodeArgs, global: &GlobalArgs) -> Result<()> {
+    if args.key.is_some() {
+        // Messages stored under a key all live in the store chunk, so the
+        // one value after FILE, which clap takes for CHUNK, is the message.
+        if args.message.is_some() {
+            return Err(format!(
+                "CHUNK cannot be given with --key, which stores the message in the {} chunk",
+                MessageStore::CHUNK_TYPE
+            )
+            .into());
+        }
+        args.message = args.chunk_type.take();
+    }
+    ChunkType::from_str(args.chunk_type())?;
+    let passphrase = match args.encrypt {
+        true => Some(passphrase(true)?),
+        false => None,
+    };
+    let Some(paths) = batch::expand(&args.file_path, args.recursive)? else {
+        encode_file(args, passphrase.as_deref(), false, global)?;
+        return Ok(());
+    };
+
+    if args.message.is_none() {
+        return Err("MESSAGE is required when FILE names several files".into());
+    }
+    batch::check_output(&args.output)?;
+    batch::run(paths, |path| {
+        let args = EncodeArgs {
+            file_path: path.to_owned(),
+            ..args.clone()
+        };
+        encode_file(args, passphrase.as_deref(), true, global)
+    })
+}
+
+/// Encodes into the single file `args.file_path`. With `skip_existing`, a
+/// file that already holds the same chunk is left alone.
+fn encode_file(
+    mut args: EncodeArgs,
+    passphrase: Option<&str>,
+    skip_existing: bool,
+    global: &GlobalArgs,
+) -> Result<Outcome> {
+    let mut png = if args.file_path == Path::new("-") {
+        if args.message.is_none() {
+            return Err("MESSAGE is required when the image is read from stdin".into());
+        }
+        carrier_from_stdin()?
+    } else {
+        read_png(&args.file_path)?
+    };
+    let original = args.plan.shows_changes().then(|| png.clone());
+    log(
+        &args.plan,
+        format_args!(
+            "read {} ({} chunks)",
+            args.file_path.display(),
+            png.chunks().len()
+        ),
+    );
+    if !args.no_interpolate {
+        if let Some(message) = &mut args.message {
+            *message = placeholders::expand(message, &args.file_path)?;
+        }
+    }
+    let mut message = read_message(args.message.take(), args.stdin_format)?;
+    log(
+        &args.plan,
+        format_args!("message is {} bytes", message.len()),
+    );
+    // Appended records and text chunks are never unwrapped as a whole, so
+    // their messages need no escaping.
+    let appends = args.append_record && png.chunk_by_type(args.chunk_type()).is_some();
+    let signing_key = args.key_file.as_ref().map(std::fs::read).transpose()?;
+    let options = EncodeOptions {
+        compress: args.compress.map(|method| match method {
+            CompressMethod::Deflate => Method::Deflate,
+            CompressMethod::Zstd => Method::Zstd,
+        }),
+        passphrase,
+        signing_key: signing_key.as_deref(),
+        embedded: args.text.is_some() || appends,
+    };
+    message = message::encode_payload(&message, &options)?;
+    log(
+        &args.plan,
+        format_args!("payload is {} bytes", message.len()),
+    );
+    if let Some(key) = &args.key {
+        let mut store = MessageStore::load(&png)?;
+        if skip_existing && store.get(key) == Some(&message[..]) {
+            return Ok(Outcome::Skipped(format!("already holds entry {}", key)));
+        }
+        store.set(key, message)?;
+        log(&args.plan, format_args!("stored entry {}", key));
+        store.save(&mut png)?;
+        return write_changes(
+            &args.file_path,
+            original.as_ref(),
+            &png,
+            &args.output,
+            &args.plan,
+            global,
+        );
+    }
+    if args.append_record {
+        if let Some(chunk) = png.chunk_by_type_mut(args.chunk_type()) {
+            let mut records = Records::parse(chunk.data())?;
+            records.push(message);
+            log(
+                &args.plan,
+                format_args!("appended record {}", records.len() - 1),
+            );
+            chunk.set_data(records.as_bytes())?;
+            return write_changes(
+                &args.file_path,
+                original.as_ref(),
+                &png,
+                &args.output,
+                &args.plan,
+                global,
+            );
+        }
+    }
+
+    if let Some(max_len) = args.split {
+        let chunk_type = ChunkType::from_str(args.chunk_type())?;
+        let parts = message::payload_chunks(chunk_type, &message, Some(max_len as usize))?;
+        log(
+            &args.plan,
+            format_args!("split into {} chunks", parts.len()),
+        );
+        png.insert_before_iend(parts);
+        return write_changes(
+            &args.file_path,
+            original.as_ref(),
+            &png,
+            &args.output,
+            &args.plan,
+            global,
+        );
+    }
+    if message.len() > Chunk::MAX_LENGTH {
+        return Err("message is too large for a single chunk, use --split".into());
+    }
+
+    let chunk_type = ChunkType::from_str(args.chunk_type())?;
+    let new_chunk = match &args.text {
+        Some(keyword) => text_chunk(chunk_type, keyword, message, args.language.clone())?,
+        None => message::payload_chunks(chunk_type, &message, None)?.remove(0),
+    };
+    let exists = png
+        .chunks()
+        .iter()
+        .any(|c| c.chunk_type() == new_chunk.chunk_type() && c.data() == new_chunk.data());
+    if skip_existing && exists {
+        return Ok(Outcome::Skipped(format!(
+            "already holds this {} chunk",
+            args.chunk_type()
+        )));
+    }
+    if args.random_position {
+        let mut rng: StdRng = match args.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => rand::make_rng(),
+        };
+        let points = png.insertion_points();
+        let index = points[rng.random_range(0..points.len())];
+        png.insert_chunk_at(index, new_chunk);
+    } else if let Some(position) = args.position {
+        match position {
+            Position::BeforeIdat => png.insert_before("IDAT", new_chunk)?,
+            Position::BeforeIend => png.insert_before("IEND", new_chunk)?,
+            Position::Index(index) if png.insertion_points().contains(&index) => {
+                png.insert_chunk_at(index, new_chunk)
+            }
+            Position::Index(index) => {
+                return Err(format!(
+                    "index {} is outside IHDR..IEND or between IDAT chunks",
+                    index
+                )
+                .into())
+            }
+        }
+    } else {
+        png.insert_before_iend([new_chunk]);
+    }
+    write_changes(
+        &args.file_path,
+        original.as_ref(),
+        &png,
+        &args.output,
+        &args.plan,
+        global,
+    )
+}
+
+pub fn decode(args: DecodeArgs, global: &GlobalArgs) -> Result<()> {
+    ChunkType::from_str(args.chunk_type())?;
+    if let Some(keyword) = &args.keyword {
+        let png = read_png(&args.file_path)?;
+        // A chunk that fails to parse may be the one asked for, so its error
+        // is reported if no other chunk matches.
+        let mut unreadable = None;
+        let chunk = png
+            .chunks_by_type(args.chunk_type())
+            .find(|c| match TextChunk::parse(c) {
+                Some(Ok(text)) => &text.keyword == keyword,
+                Some(Err(e)) => {
+                    unreadable.get_or_insert(e);
+                    false
+                }
+                None => false,
+            });
+        let chunk = match (chunk, unreadable) {
+            (Some(chunk), _) => chunk,
+            (None, Some(e)) => return Err(e.into()),
+            (None, None) => {
+                let message = format!("no {} chunk with keyword {}", args.chunk_type(), keyword);
+                return Err(PngMeError::ChunkNotFound(message.into()).into());
+            }
+        };
+        if global.format == OutputFormat::Json {
+            return print_json(&DecodedChunk::new(chunk));
+        }
+        println!("{}", display_chunk(chunk, global));
+        return Ok(());
+    }
+
+    let json = global.format == OutputFormat::Json;
+    if args.select.all {
+        let png = read_png(&args.file_path)?;
+        let mut select = selector(args.chunk_type(), &args.select);
+        let chunks: Vec<Chunk> = png
+            .chunks()
+            .iter()
+            .filter(|c| select(c))
+            .map(|c| decoded(&joined(c.clone()), &DecodeOptions::default()))
+            .collect::<Result<_>>()?;
+        if chunks.is_empty() {
+            return Err(no_matching_chunk(args.chunk_type()));
+        }
+        if json {
+            let chunks: Vec<DecodedChunk> = chunks.iter().map(DecodedChunk::new).collect();
+            return print_json(&chunks);
+        }
+        for chunk in &chunks {
+            println!("{}", display_chunk(chunk, global));
+        }
+        return Ok(());
+    }
+
+    let chunk = if let Some(key) = &args.key {
+        let store = MessageStore::load(&read_png(&args.file_path)?)?;
+        let value = store.get(key).ok_or_else(|| store::no_entry(key))?;
+        Chunk::new(ChunkType::from_str(args.chunk_type())?, value.to_vec())
+    } else if args.select.is_set() {
+        let mut png = read_png(&args.file_path)?;
+        let chunk = png
+            .remove_chunks_where(selector(args.chunk_type(), &args.select))
+            .pop()
+            .ok_or_else(|| no_matching_chunk(args.chunk_type()))?;
+        joined(chunk)
+    } else if global.chunk_index && args.file_path != Path::new("-") {
+        indexed_chunk(&args.file_path, args.chunk_type())?
+    } else {
+        let png = read_png(&args.file_path)?;
+        let payload = message::read_payload(&png, args.chunk_type())?
+            .ok_or_else(|| no_matching_chunk(args.chunk_type()))?;
+        Chunk::try_new(ChunkType::from_str(args.chunk_type())?, payload)?
+    };
+    let verify_key = match (args.verify, &args.key_file) {
+        (true, Some(key)) => Some(std::fs::read(key)?),
+        _ => None,
+    };
+    let passphrase = match args.decrypt {
+        true => Some(passphrase(false)?),
+        false => None,
+    };
+    let options = DecodeOptions {
+        passphrase: passphrase.as_deref(),
+        verify_key: verify_key.as_deref(),
+    };
+    let chunk = decoded(&chunk, &options)?;
+    if args.decrypt {
+        if json {
+            return print_json(&chunk);
+        }
+        std::io::stdout().write_all(chunk.data())?;
+        println!();
+    } else if args.records {
+        let records = Records::parse(chunk.data())?;
+        if json {
+            let records: Vec<DecodedRecord> = records
+                .iter()
+                .enumerate()
+                .map(|(idx, record)| DecodedRecord::new(idx, record))
+                .collect();
+            return print_json(&records);
+        }
+        for (idx, record) in records.iter().enumerate() {
+            println!(
+                "{:>4} {:>10} {}",
+                idx,
+                format_size(record.len() as u64, global),
+                String::from_utf8_lossy(record)
+            );
+        }
+    } else if let Some(idx) = args.record {
+        let records = Records::parse(chunk.data())?;
+        let record = records.get(idx)?;
+        if json {
+            return print_json(&DecodedRecord::new(idx, record));
+        }
+        std::io::stdout().write_all(record)?;
+        println!();
+    } else if json {
+        return print_json(&DecodedChunk::new(&chunk));
+    } else {
+        println!("{}", display_chunk(&chunk, global));
+    }
+    Ok(())
+}
+
+pub fn remove(args: RemoveArgs, global: &GlobalArgs) -> Result<()> {
+    ChunkType::from_str(args.chunk_type())?;
+    let not_found = match &args.key {
+        Some(key) => store::no_entry(key),
+        None => no_matching_chunk(args.chunk_type()),
+    };
+    if let Some(paths) = batch::expand(&args.file_path, args.recursive)? {
+        batch::check_output(&args.output)?;
+        return batch::run(paths, |path| {
+            Ok(match remove_from(path, &args, global)?.len() {
+                0 => Outcome::Skipped(not_found.to_string()),
+                _ if args.plan.dry_run => Outcome::DryRun,
+                _ => Outcome::Modified,
+            })
+        });
+    }
+
+    let removed = remove_from(&args.file_path, &args, global)?;
+    if removed.is_empty() {
+        return Err(not_found);
+    }
+    if !args.plan.dry_run {
+        for chunk in &removed {
+            eprintln!("Removed: {}", display_chunk(chunk, global));
+        }
+    }
+    Ok(())
+}
+
+/// Removes the chunks selected by `args` from the file at `path` and returns
+/// them. The file is only rewritten when something was removed.
+fn remove_from(path: &Path, args: &RemoveArgs, global: &GlobalArgs) -> Result<Vec<Chunk>> {
+    let mut png = read_png(path)?;
+    let original = args.plan.shows_changes().then(|| png.clone());
+    log(
+        &args.plan,
+        format_args!("read {} ({} chunks)", path.display(), png.chunks().len()),
+    );
+    let removed = match &args.key {
+        Some(key) => {
+            let mut store = MessageStore::load(&png)?;
+            let value = store.remove(key);
+            store.save(&mut png)?;
+            let chunk_type = ChunkType::from_str(args.chunk_type())?;
+            value
+                .map(|value| Chunk::new(chunk_type, value))
+                .into_iter()
+                .collect()
+        }
+        None => png.remove_chunks_where(selector(args.chunk_type(), &args.select)),
+    };
+    log(
+        &args.plan,
+        format_args!("selected {} {} chunks", removed.len(), args.chunk_type()),
+    );
+    if !removed.is_empty() {
+        write_changes(
+            path,
+            original.as_ref(),
+            &png,
+            &args.output,
+            &args.plan,
+            global,
+        )?;
+    }
+    Ok(removed)
+}
+
+fn no_matching_chunk(chunk_type: &str) -> pngme::Error {
+    PngMeError::ChunkNotFound(format!("no matching {} chunk", chunk_type).into()).into()
+}
+
+/// A predicate for the chunks of `chunk_type` picked by `select`, to be
+/// called on every chunk in file order.
+fn selector<'a>(chunk_type: &'a str, select: &'a SelectArgs) -> impl FnMut(&Chunk) -> bool + 'a {
+    let mut seen = 0;
+    move |chunk| {
+        if chunk.chunk_type().to_string() != chunk_type {
+            return false;
+        }
+        if let Some(needle) = &select.match_data {
+            if memchr::memmem::find(chunk.data(), needle.as_bytes()).is_none() {
+                return false;
+            }
+        }
+        seen += 1;
+        select.all || seen - 1 == select.index.unwrap_or(0)
+    }
+}
+
+pub fn layout(args: LayoutArgs) -> Result<()> {
+    let png = read_png(&args.file_path)?;
+    let diagram = match args.format {
+        LayoutFormat::Svg => layout::svg(&png),
+        LayoutFormat::Dot => layout::dot(&png),
+    };
+    print!("{}", diagram);
+    Ok(())
+}
+
+pub fn print(args: PrintArgs, global: &GlobalArgs) -> Result<()> {
+    let png = read_png(&args.file_path)?;
+    if global.format == OutputFormat::Json {
+        return print_json(&PngReport::new(&png));
+    }
+    let chunks: Vec<String> = png
+        .chunks()
+        .iter()
+        .map(|c| display_chunk(c, global))
+        .collect();
+    println!("Png{{ {} }}", chunks.join(",\n"));
+    Ok(())
+}
+
+/// A decoded chunk for `--format json`: the chunk, and the keyword and text
+/// of text chunks.
+#[derive(Serialize)]
+struct DecodedChunk<'a> {
+    #[serde(flatten)]
+    chunk: &'a Chunk,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<TextChunk>,
+}
+
+impl<'a> DecodedChunk<'a> {
+    fn new(chunk: &'a Chunk) -> DecodedChunk<'a> {
+        DecodedChunk {
+            chunk,
+            text: TextChunk::parse(chunk).and_then(|text| text.ok()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DecodedRecord {
+    index: usize,
+    length: usize,
+    encoding: &'static str,
+    data: String,
+}
+
+impl DecodedRecord {
+    fn new(index: usize, record: &[u8]) -> DecodedRecord {
+        let (encoding, data) = encode_data(record);
+        DecodedRecord {
+            index,
+            length: record.len(),
+            encoding,
+            data,
+        }
+    }
+}
+
+fn print_json(value: &impl Serialize) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+/// A byte count for a table column: the bare number, or a size in binary
+/// units when `--human-readable` is given.
+fn format_size(bytes: u64, global: &GlobalArgs) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if !global.human_readable {
+        return bytes.to_string();
+    }
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// A byte count for running text, e.g. "42 bytes" or "1.5 MiB".
+fn describe_size(bytes: u64, global: &GlobalArgs) -> String {
+    if global.human_readable {
+        format_size(bytes, global)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+/// Like the `Display` of `Chunk`, honouring `--human-readable` for the length
+/// and showing text chunks as keyword and text.
+fn display_chunk(chunk: &Chunk, global: &GlobalArgs) -> String {
+    let data = match TextChunk::parse(chunk) {
+        Some(Ok(text)) => format!("text: '{}'", shorten(text.to_string())),
+        _ => format!("data: '{}'", chunk.data_as_string().unwrap()),
+    };
+    format!(
+        "Chunk{{type: {}, {}, len: {}}}",
+        chunk.chunk_type(),
+        data,
+        format_size(chunk.length() as u64, global)
+    )
+}
+
+/// Cuts `text` short after [`DISPLAY_TEXT_CHARS`] characters, the way
+/// [`Chunk::data_as_string`] cuts long data.
+fn shorten(text: String) -> String {
+    match text.char_indices().nth(DISPLAY_TEXT_CHARS) {
+        Some((end, _)) => format!("{}[.. {} more bytes ..]", &text[..end], text.len() - end),
+        None => text,
+    }
+}
+
+/// A tEXt, zTXt or iTXt chunk holding `message` under `keyword`.
+fn text_chunk(
+    chunk_type: ChunkType,
+    keyword: &str,
+    message: Vec<u8>,
+    language: Option<String>,
+) -> Result<Chunk> {
+    let kind = match chunk_type {
+        ChunkType::TEXT => TextKind::Text,
+        ChunkType::ZTXT => TextKind::Compressed,
+        ChunkType::ITXT => TextKind::International,
+        _ => return Err("--text needs a tEXt, zTXt or iTXt chunk".into()),
+    };
+    if language.is_some() && kind != TextKind::International {
+        return Err("--language needs an iTXt chunk".into());
+    }
+
+    let message = String::from_utf8(message).map_err(|_| TextError::InvalidUtf8)?;
+    let mut text = TextChunk::new(kind, keyword, &message);
+    text.language = language.unwrap_or_default();
+    Ok(text.to_chunk()?)
+}
+
+/// Returns the message given on the command line, or reads it from stdin:
+/// interactively when stdin is a terminal, otherwise the whole pipe. Stdin is
+/// decoded according to `format`, ignoring whitespace for the text encodings.
+fn read_message(message: Option<String>, format: StdinFormat) -> Result<Vec<u8>> {
+    if let Some(message) = message {
+        return Ok(message.into_bytes());
+    }
+
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprintln!("Enter the message, then press Ctrl-D on an empty line to finish:");
+    }
+    let mut message = vec![];
+    stdin.read_to_end(&mut message)?;
+
+    let text = || -> Vec<u8> {
+        message
+            .iter()
+            .copied()
+            .filter(|b| !b.is_ascii_whitespace())
+            .collect()
+    };
+    match format {
+        StdinFormat::Raw => Ok(message),
+        StdinFormat::Base64 => Ok(base64::engine::general_purpose::STANDARD.decode(text())?),
+        StdinFormat::Hex => Ok(hex::decode(text())?),
+    }
+}
+
+/// The passphrase from `PNGME_PASSPHRASE`, or else read from the terminal
+/// without echo, twice when `confirm` is set.
+fn passphrase(confirm: bool) -> Result<String> {
+    if let Ok(passphrase) = std::env::var("PNGME_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+    let passphrase = rpassword::prompt_password("Passphrase: ")?;
+    if confirm && rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+        return Err("passphrases do not match".into());
+    }
+    Ok(passphrase)
+}
+
+/// `chunk` holding its payload; see [`message::chunk_payload`].
+fn joined(chunk: Chunk) -> Chunk {
+    match message::chunk_payload(&chunk) {
+        Cow::Owned(payload) => Chunk::new(*chunk.chunk_type(), payload),
+        Cow::Borrowed(_) => chunk,
+    }
+}
+
+/// `chunk` holding the message its payload unwraps to, with a warning when
+/// a signature is stripped unchecked.
+fn decoded(chunk: &Chunk, options: &DecodeOptions) -> Result<Chunk> {
+    if options.verify_key.is_none() && chunk::is_signed(chunk.data()) {
+        eprintln!("warning: the message is signed, pass --verify --key-file KEYFILE to check it");
+    }
+    let message = message::decode_payload(chunk.data(), options)?;
+    Ok(Chunk::new(*chunk.chunk_type(), message))
+}
+
+/// All `.png` and `.png.gz` files below `dir`, in a stable order.
+fn png_files(dir: &Path) -> Vec<PathBuf> {
+    png_files_to_depth(dir, usize::MAX)
+}
+
+/// Like [`png_files`], descending at most `max_depth` directories.
+fn png_files_to_depth(dir: &Path, max_depth: usize) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(dir)
+        .max_depth(max_depth)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            let name = path.to_string_lossy().to_ascii_lowercase();
+            name.ends_with(".png") || name.ends_with(".png.gz")
+        })
+        .collect()
+}
+
+/// The PNG piped to stdin, or one built from a piped Netpbm image.
+fn carrier_from_stdin() -> Result<Png> {
+    let image = storage::read(Path::new("-"))?;
+    if image.starts_with(&Png::STANDARD_HEADER) {
+        return Ok(Png::try_from(image.as_slice())?);
+    }
+    Ok(netpbm::decode(&image)?.to_png())
+}
+
+/// The first chunk of `chunk_type` in the file at `path`, located through the
+/// `.pngidx` sidecar next to it. A missing or stale sidecar is rebuilt.
+fn indexed_chunk(path: &Path, chunk_type: &str) -> Result<Chunk> {
+    let bytes = storage::read(path)?;
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".pngidx");
+    let sidecar = PathBuf::from(sidecar);
+
+    let index = match std::fs::read(&sidecar)
+        .ok()
+        .and_then(|json| serde_json::from_slice::<ChunkIndex>(&json).ok())
+    {
+        Some(index) if index.matches(&bytes) => index,
+        _ => {
+            let index = ChunkIndex::build(&bytes)?;
+            if !storage::is_remote(path) {
+                std::fs::write(&sidecar, serde_json::to_vec(&index)?)?;
+            }
+            index
+        }
+    };
+    Ok(index
+        .chunk(&bytes, chunk_type)
+        .ok_or(PngError::ChunkNotFound)??)
+}
+
+fn read_png(path: &Path) -> Result<Png> {
+    let reader = storage::open(path)
+        .map_err(|e| PngMeError::Io(format!("cannot read {}: {}", path.display(), e).into()))?;
+    Png::from_reader(reader)
+}
+
+/// Prints a step of a command with `--verbose`.
+fn log(plan: &PlanArgs, message: std::fmt::Arguments) {
+    if plan.verbose {
+        eprintln!("{}", message);
+    }
+}
+
+/// Writes `png`, an edited copy of `original`, like [`write_png`]. With
+/// `--verbose` or `--dry-run` the changed chunks and the change in file size
+/// are listed first; `--dry-run` stops there. `original` is only needed, and
+/// only worth cloning, when [`PlanArgs::shows_changes`].
+fn write_changes(
+    path: &Path,
+    original: Option<&Png>,
+    png: &Png,
+    output: &OutputArgs,
+    plan: &PlanArgs,
+    global: &GlobalArgs,
+) -> Result<Outcome> {
+    if let Some(original) = original.filter(|_| plan.shows_changes()) {
+        eprintln!("Changes to {}:", path.display());
+        let size = |chunk: &Chunk| describe_size(chunk.length() as u64, global);
+        for change in pngme::diff::diff(original, png) {
+            let line = match change {
+                Change::Added(new) => format!(
+                    "insert {} at offset {}, {}",
+                    new.chunk.chunk_type(),
+                    new.offset,
+                    size(new.chunk)
+                ),
+                Change::Removed(old) => format!(
+                    "remove {} at offset {}, {}",
+                    old.chunk.chunk_type(),
+                    old.offset,
+                    size(old.chunk)
+                ),
+                Change::Modified { old, new } => format!(
+                    "modify {} at offset {}, {} -> {}",
+                    new.chunk.chunk_type(),
+                    match old.offset == new.offset {
+                        true => old.offset.to_string(),
+                        false => format!("{} -> {}", old.offset, new.offset),
+                    },
+                    size(old.chunk),
+                    size(new.chunk)
+                ),
+            };
+            eprintln!("  {}", line);
+        }
+        let (before, after) = (original.byte_len(), png.byte_len());
+        eprintln!(
+            "  file size {} -> {} ({}{})",
+            describe_size(before as u64, global),
+            describe_size(after as u64, global),
+            if after < before { "-" } else { "+" },
+            describe_size(after.abs_diff(before) as u64, global)
+        );
+    }
+    if plan.dry_run {
+        eprintln!("Dry run, nothing written");
+        return Ok(Outcome::DryRun);
+    }
+    write_png(path, png, output, global)?;
+    let target = match writes_to_stdout(path, output) {
+        true => "stdout".to_string(),
+        false => output.path.as_deref().unwrap_or(path).display().to_string(),
+    };
+    log(plan, format_args!("wrote {}", target));
+    Ok(Outcome::Modified)
+}
+
+/// Whether [`write_png`] sends the PNG for `path` to stdout, where nothing
+/// else may then be printed.
+fn writes_to_stdout(path: &Path, output: &OutputArgs) -> bool {
+    let target = match output.emit {
+        Some(_) => output.path.as_deref(),
+        None => output.path.as_deref().or(Some(path)),
+    };
+    target.is_none_or(|target| target == Path::new("-"))
+}
+
+/// Writes `png` to `--output` if given, replacing the input `path` otherwise.
+/// With `--emit` the PNG is rendered as source code, which goes to stdout
+/// unless `--output` is given.
+fn write_png(path: &Path, png: &Png, output: &OutputArgs, global: &GlobalArgs) -> Result<()> {
+    let stdout = Path::new("-");
+    let emitted = output.emit.map(|format| {
+        let bytes = png.as_bytes();
+        let name = match path.file_name() {
+            Some(name) if path != stdout => name.to_string_lossy(),
+            _ => "png".into(),
+        };
+        match format {
+            EmitFormat::CArray => emit::c_array(&bytes, &emit::identifier(&name)),
+            EmitFormat::RustBytes => emit::rust_bytes(&bytes),
+        }
+        .into_bytes()
+    });
+    let target = match emitted {
+        Some(_) => output.path.as_deref().unwrap_or(stdout),
+        None => output.path.as_deref().unwrap_or(path),
+    };
+
+    if target == stdout {
+        let mut bytes = emitted.unwrap_or_else(|| png.as_bytes());
+        if global.gzip && output.emit.is_none() {
+            bytes = storage::gzip(&bytes)?;
+        }
+        let mut stdout = std::io::stdout();
+        stdout.write_all(&bytes)?;
+        return Ok(stdout.flush()?);
+    }
+
+    if output.path.is_some() && !output.force && !storage::is_remote(target) && target.exists() {
+        return Err(format!(
+            "{} already exists, pass --force to overwrite it",
+            target.display()
+        )
+        .into());
+    }
+    if output.backup {
+        if storage::is_remote(target) {
+            return Err("--backup only works for local files".into());
+        }
+        if target.exists() {
+            storage::backup(target)?;
+        }
+    }
+    if output.trash {
+        if storage::is_remote(target) {
+            return Err("--trash only works for local files".into());
+        }
+        if target.exists() {
+            trash::delete(target)?;
+        }
+    }
+
+    match emitted {
+        Some(bytes) => storage::write(target, &bytes),
+        None => storage::write_with(target, |writer| png.write_to(writer)),
+    }
+}
@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+
+use pngme::Result;
+
+use super::png_files_to_depth;
+use crate::args::OutputArgs;
+
+/// What processing one file of a batch did to it.
+pub enum Outcome {
+    Modified,
+    /// Would have been modified, but this is a dry run.
+    DryRun,
+    /// Left untouched, with the reason.
+    Skipped(String),
+}
+
+/// The files named by `path` when it is a directory or a glob pattern, or
+/// `None` when it names a single file. Directories contribute the PNG files
+/// directly inside them, or below them when `recursive`. A pattern that
+/// matches nothing is an error.
+pub fn expand(path: &Path, recursive: bool) -> Result<Option<Vec<PathBuf>>> {
+    let depth = if recursive { usize::MAX } else { 1 };
+    if path.is_dir() {
+        return Ok(Some(png_files_to_depth(path, depth)));
+    }
+    let pattern = path.to_string_lossy();
+    if path.exists() || !pattern.contains(['*', '?', '[']) {
+        return Ok(None);
+    }
+
+    let mut paths = vec![];
+    for entry in glob::glob(&pattern)? {
+        let entry = entry?;
+        if entry.is_dir() {
+            paths.extend(png_files_to_depth(&entry, depth));
+        } else {
+            paths.push(entry);
+        }
+    }
+    if paths.is_empty() {
+        return Err(format!("no files match {}", pattern).into());
+    }
+    Ok(Some(paths))
+}
+
+/// Rejects output options that only make sense for a single file.
+pub fn check_output(output: &OutputArgs) -> Result<()> {
+    if output.path.is_some() {
+        return Err("--output cannot be used when FILE names several files".into());
+    }
+    // Without an output file, every file's source would go to stdout, one
+    // after another.
+    if output.emit.is_some() {
+        return Err("--emit cannot be used when FILE names several files".into());
+    }
+    Ok(())
+}
+
+/// Runs `job` on every file in turn, carrying on past failures, and prints a
+/// line per file and a summary. Fails if any file failed.
+pub fn run(paths: Vec<PathBuf>, mut job: impl FnMut(&Path) -> Result<Outcome>) -> Result<()> {
+    let (mut modified, mut planned, mut skipped, mut failed) = (0, 0, 0, 0);
+    for path in &paths {
+        match job(path) {
+            Ok(Outcome::Modified) => {
+                modified += 1;
+                println!("modified {}", path.display());
+            }
+            Ok(Outcome::DryRun) => {
+                planned += 1;
+                println!("would modify {}", path.display());
+            }
+            Ok(Outcome::Skipped(reason)) => {
+                skipped += 1;
+                println!("skipped  {}: {}", path.display(), reason);
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("failed   {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    match planned {
+        0 => println!(
+            "{} modified, {} skipped, {} failed",
+            modified, skipped, failed
+        ),
+        _ => println!(
+            "{} would be modified, {} skipped, {} failed (dry run)",
+            planned, skipped, failed
+        ),
+    }
+    if failed > 0 {
+        return Err(format!("{} of {} files failed", failed, paths.len()).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,90 @@
+use sha2::Digest;
+
+use pngme::{pixels::Pixels, ChunkType, Png, Result};
+
+use crate::args::{ChecksumArgs, DigestAlgorithm};
+
+pub fn checksum(args: ChecksumArgs) -> Result<()> {
+    let file_content = crate::storage::read(&args.file_path)?;
+    let png = Png::try_from(&file_content[..])?;
+
+    for (idx, chunk) in png.chunks().iter().enumerate() {
+        println!(
+            "{:>4} {} {}",
+            idx,
+            chunk.chunk_type(),
+            hex_digest(args.algo, &chunk.as_bytes())
+        );
+    }
+    if args.pixels {
+        println!("pixels    {}", pixel_digest(args.algo, &png)?);
+    }
+    println!("file      {}", hex_digest(args.algo, &file_content));
+
+    Ok(())
+}
+
+pub fn hex_digest(algo: DigestAlgorithm, bytes: &[u8]) -> String {
+    let digest = match algo {
+        DigestAlgorithm::Sha256 => sha2::Sha256::digest(bytes).to_vec(),
+        DigestAlgorithm::Blake3 => blake3::hash(bytes).as_bytes().to_vec(),
+    };
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Digest of the decoded image, so that files differing only in metadata or
+/// compression hash the same. The image geometry is hashed along with the
+/// samples, and so are PLTE and tRNS, which decide the colours the samples
+/// stand for.
+pub fn pixel_digest(algo: DigestAlgorithm, png: &Png) -> Result<String> {
+    let pixels = Pixels::decode(png)?;
+    let ihdr = &pixels.ihdr;
+    let mut bytes = ihdr.width.to_be_bytes().to_vec();
+    bytes.extend(ihdr.height.to_be_bytes());
+    bytes.extend([ihdr.bit_depth, ihdr.color_type]);
+    for chunk_type in [ChunkType::PLTE, ChunkType::TRNS] {
+        let data = png
+            .chunks()
+            .iter()
+            .find(|c| *c.chunk_type() == chunk_type)
+            .map_or(&[][..], |c| c.data());
+        bytes.extend((data.len() as u32).to_be_bytes());
+        bytes.extend(data);
+    }
+    bytes.extend(&pixels.data);
+    Ok(hex_digest(algo, &bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pngme::{zlib, Chunk};
+
+    fn paletted_png(palette: &[u8]) -> Png {
+        let mut ihdr = 2u32.to_be_bytes().to_vec();
+        ihdr.extend(1u32.to_be_bytes());
+        ihdr.extend([8, 3, 0, 0, 0]);
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::IHDR, ihdr),
+            Chunk::new(ChunkType::PLTE, palette.to_vec()),
+            Chunk::new(ChunkType::IDAT, zlib::deflate(&[0, 0, 1])),
+            Chunk::new(ChunkType::IEND, vec![]),
+        ])
+    }
+
+    #[test]
+    fn test_pixel_digest_covers_palette() {
+        let red_green = paletted_png(&[255, 0, 0, 0, 255, 0]);
+        let blue_white = paletted_png(&[0, 0, 255, 255, 255, 255]);
+        for algo in [DigestAlgorithm::Sha256, DigestAlgorithm::Blake3] {
+            assert_eq!(
+                pixel_digest(algo, &red_green).unwrap(),
+                pixel_digest(algo, &red_green.clone()).unwrap()
+            );
+            assert_ne!(
+                pixel_digest(algo, &red_green).unwrap(),
+                pixel_digest(algo, &blue_white).unwrap()
+            );
+        }
+    }
+}
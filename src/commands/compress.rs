@@ -0,0 +1,88 @@
+use std::io::Write;
+
+use pngme::{zlib, Chunk, ChunkType, Result};
+
+use super::{describe_size, format_size, writes_to_stdout};
+use crate::args::{CompressArgs, GlobalArgs};
+
+pub fn compress(args: CompressArgs, global: &GlobalArgs) -> Result<()> {
+    let mut png = super::read_png(&args.file_path)?;
+    // The report goes to stderr when the PNG itself is written to stdout.
+    let to_stdout = args.apply && writes_to_stdout(&args.file_path, &args.output);
+    let mut out: Box<dyn Write> = match to_stdout {
+        true => Box::new(std::io::stderr()),
+        false => Box::new(std::io::stdout()),
+    };
+
+    writeln!(
+        out,
+        "{:>4} {:<4} {:>10} {:>10} {:>10}  action",
+        "idx", "type", "size", "compressed", "saving"
+    )?;
+    let mut total_saving = 0;
+    let mut converted = 0;
+    for (idx, chunk) in png.chunks_mut().iter_mut().enumerate() {
+        let chunk_type = *chunk.chunk_type();
+        let (candidate, action) = if chunk_type == ChunkType::TEXT {
+            (text_to_ztxt(chunk)?, "tEXt -> zTXt")
+        } else if !chunk.chunk_type().is_critical() && !chunk.chunk_type().is_public() {
+            // Private payloads are only reported: decoders of those chunks
+            // would not know the data has been compressed.
+            let data = zlib::deflate(chunk.data());
+            (Chunk::new(chunk_type, data), "report only")
+        } else {
+            continue;
+        };
+
+        let saving = chunk.length() as i64 - candidate.length() as i64;
+        writeln!(
+            out,
+            "{:>4} {:<4} {:>10} {:>10} {:>10}  {}",
+            idx,
+            chunk_type.to_string(),
+            format_size(chunk.length() as u64, global),
+            format_size(candidate.length() as u64, global),
+            if saving < 0 {
+                format!("-{}", format_size(saving.unsigned_abs(), global))
+            } else {
+                format_size(saving as u64, global)
+            },
+            if saving > 0 { action } else { "no gain" }
+        )?;
+        if saving > 0 {
+            total_saving += saving;
+            if args.apply && chunk_type == ChunkType::TEXT {
+                *chunk = candidate;
+                converted += 1;
+            }
+        }
+    }
+    writeln!(
+        out,
+        "Potential saving: {}",
+        describe_size(total_saving as u64, global)
+    )?;
+
+    if converted > 0 {
+        writeln!(out, "Converted {} tEXt chunks to zTXt", converted)?;
+    }
+    if converted > 0 || to_stdout {
+        super::write_png(&args.file_path, &png, &args.output, global)?;
+    }
+    Ok(())
+}
+
+/// The zTXt equivalent of a tEXt chunk: same keyword, compressed text.
+fn text_to_ztxt(chunk: &Chunk) -> Result<Chunk> {
+    let data = chunk.data();
+    let nul = data
+        .iter()
+        .position(|&b| b == 0)
+        .ok_or("tEXt chunk without keyword separator")?;
+
+    // keyword, NUL, compression method 0 (deflate), stream
+    let mut ztxt = data[..=nul].to_vec();
+    ztxt.push(0);
+    ztxt.extend(zlib::deflate(&data[nul + 1..]));
+    Ok(Chunk::new(ChunkType::ZTXT, ztxt))
+}
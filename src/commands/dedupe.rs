@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use pngme::Result;
+
+use super::{checksum::pixel_digest, png_files};
+use crate::args::{DedupeImagesArgs, DigestAlgorithm};
+use crate::storage;
+
+pub fn dedupe_images(args: DedupeImagesArgs) -> Result<()> {
+    let mut groups: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for path in png_files(&args.dir) {
+        match pixel_hash(&path) {
+            Ok(digest) => groups.entry(digest).or_default().push(path),
+            Err(e) => eprintln!("Skipping {}: {}", path.display(), e),
+        }
+    }
+
+    let duplicates = groups.into_values().filter(|paths| paths.len() > 1);
+    for (idx, paths) in duplicates.enumerate() {
+        println!("group {} ({} files):", idx + 1, paths.len());
+        for path in &paths {
+            println!("  {}", path.display());
+        }
+
+        if args.hardlink {
+            // A link would give a .gz file raw PNG bytes or the other way
+            // round, so files are only linked to one stored the same way.
+            let (gzipped, raw): (Vec<_>, Vec<_>) =
+                paths.iter().partition(|path| storage::is_gzip(path));
+            for same_encoding in [gzipped, raw] {
+                if let Some((original, copies)) = same_encoding.split_first() {
+                    for copy in copies {
+                        hardlink(original, copy)?;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn pixel_hash(path: &Path) -> Result<String> {
+    let png = super::read_png(path)?;
+    pixel_digest(DigestAlgorithm::Blake3, &png)
+}
+
+/// Replaces `copy` with a hard link to `original`, going through a temporary
+/// name so `copy` is never missing.
+fn hardlink(original: &Path, copy: &Path) -> Result<()> {
+    let tmp = copy.with_extension("png.pngme-link");
+    std::fs::hard_link(original, &tmp)?;
+    std::fs::rename(&tmp, copy)?;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use pngme::diff::{self, Change, Located};
+use pngme::Result;
+
+use super::{format_size, read_png};
+use crate::args::{DiffArgs, GlobalArgs};
+
+pub fn diff(args: DiffArgs, global: &GlobalArgs) -> Result<()> {
+    let old = read_png(&args.old_path)?;
+    let new = read_png(&args.new_path)?;
+    let changes = diff::diff(&old, &new);
+
+    let describe = |chunk: &Located| {
+        format!(
+            "offset {}, length {}, crc {:08x}",
+            chunk.offset,
+            format_size(chunk.chunk.length() as u64, global),
+            chunk.chunk.crc()
+        )
+    };
+    let (mut added, mut removed, mut modified) = (0, 0, 0);
+    for change in &changes {
+        let (old_data, new_data): (&[u8], &[u8]) = match change {
+            Change::Added(new) => {
+                added += 1;
+                println!("+ {}  {}", new.chunk.chunk_type(), describe(new));
+                (&[], new.chunk.data())
+            }
+            Change::Removed(old) => {
+                removed += 1;
+                println!("- {}  {}", old.chunk.chunk_type(), describe(old));
+                (old.chunk.data(), &[])
+            }
+            Change::Modified { old, new } => {
+                modified += 1;
+                println!("~ {}  {}", old.chunk.chunk_type(), describe(old));
+                println!("  {}  {}", new.chunk.chunk_type(), describe(new));
+                (old.chunk.data(), new.chunk.data())
+            }
+        };
+        if args.data {
+            for line in diff::hex_diff(old_data, new_data) {
+                println!("    {}", line);
+            }
+        }
+    }
+
+    println!(
+        "{} added, {} removed, {} modified",
+        added, removed, modified
+    );
+    Ok(())
+}
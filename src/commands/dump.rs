@@ -0,0 +1,55 @@
+use std::str::FromStr;
+
+use pngme::{chunk, ChunkType, Result};
+
+use super::{no_matching_chunk, read_png, selector};
+use crate::args::DumpArgs;
+
+pub fn dump(args: DumpArgs) -> Result<()> {
+    ChunkType::from_str(&args.chunk_type)?;
+    let png = read_png(&args.file_path)?;
+    let mut select = selector(&args.chunk_type, &args.select);
+    let chunks: Vec<_> = png
+        .chunks_with_offsets()
+        .filter(|(_, chunk)| select(chunk))
+        .collect();
+    if chunks.is_empty() {
+        return Err(no_matching_chunk(&args.chunk_type));
+    }
+
+    for (idx, (offset, chunk)) in chunks.into_iter().enumerate() {
+        let data = chunk.data();
+        let (start, end) = match args.range {
+            Some(range) => (range.start, range.end.unwrap_or(data.len()).min(data.len())),
+            None => (0, data.len()),
+        };
+        if start > data.len() {
+            return Err(format!(
+                "range starts past the end of the {}-byte {} chunk",
+                data.len(),
+                args.chunk_type
+            )
+            .into());
+        }
+        if idx > 0 {
+            println!();
+        }
+        // The data follows the length and type fields.
+        let data_offset = offset + 8;
+        println!(
+            "{} at offset {}, {} bytes",
+            chunk.chunk_type(),
+            offset,
+            data.len()
+        );
+        print!(
+            "{}",
+            chunk::hexdump(
+                &data[start..end],
+                args.width as usize,
+                data_offset + start as u64
+            )
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use pngme::{magic, Result};
+
+use super::png_files;
+use crate::args::{IndexArgs, IndexBuildArgs, IndexCommand, IndexQueryArgs};
+
+const INDEX_FILE: &str = ".pngme-index";
+const INDEX_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Default)]
+struct Index {
+    version: u32,
+    /// Entries keyed by path relative to the indexed directory.
+    files: BTreeMap<String, IndexEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+struct IndexEntry {
+    len: u64,
+    modified: u64,
+    chunk_types: BTreeSet<String>,
+    keywords: BTreeSet<String>,
+    magics: BTreeSet<String>,
+}
+
+pub fn index(args: IndexArgs) -> Result<()> {
+    match args.command {
+        IndexCommand::Build(args) => build(args),
+        IndexCommand::Query(args) => query(args),
+    }
+}
+
+fn build(args: IndexBuildArgs) -> Result<()> {
+    let old = load(&args.dir).unwrap_or_default();
+    let mut index = Index {
+        version: INDEX_VERSION,
+        files: BTreeMap::new(),
+    };
+
+    let mut updated = 0;
+    for path in png_files(&args.dir) {
+        let key = path.strip_prefix(&args.dir)?.to_string_lossy().to_string();
+        let metadata = std::fs::metadata(&path)?;
+        let len = metadata.len();
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        if let Some(entry) = old.files.get(&key) {
+            if entry.len == len && entry.modified == modified {
+                index.files.insert(key, entry.clone());
+                continue;
+            }
+        }
+
+        match index_file(&path, len, modified) {
+            Ok(entry) => {
+                index.files.insert(key, entry);
+                updated += 1;
+            }
+            Err(e) => eprintln!("Skipping {}: {}", path.display(), e),
+        }
+    }
+
+    let removed = old
+        .files
+        .keys()
+        .filter(|key| !index.files.contains_key(*key))
+        .count();
+    std::fs::write(args.dir.join(INDEX_FILE), serde_json::to_vec(&index)?)?;
+    println!(
+        "Indexed {} files ({} updated, {} removed)",
+        index.files.len(),
+        updated,
+        removed
+    );
+    Ok(())
+}
+
+fn query(args: IndexQueryArgs) -> Result<()> {
+    let index = load(&args.dir)?;
+    for (path, entry) in &index.files {
+        let matches = args
+            .chunk_type
+            .as_ref()
+            .is_none_or(|t| entry.chunk_types.contains(t))
+            && args
+                .keyword
+                .as_ref()
+                .is_none_or(|k| entry.keywords.contains(k))
+            && args.magic.as_ref().is_none_or(|m| entry.magics.contains(m));
+        if matches {
+            println!("{}", args.dir.join(path).display());
+        }
+    }
+    Ok(())
+}
+
+fn load(dir: &Path) -> Result<Index> {
+    let bytes = std::fs::read(dir.join(INDEX_FILE))
+        .map_err(|e| format!("cannot read index of {}: {}", dir.display(), e))?;
+    let index: Index = serde_json::from_slice(&bytes)?;
+    if index.version != INDEX_VERSION {
+        return Err(format!("unsupported index version {}", index.version).into());
+    }
+    Ok(index)
+}
+
+fn index_file(path: &Path, len: u64, modified: u64) -> Result<IndexEntry> {
+    let png = super::read_png(path)?;
+
+    let mut entry = IndexEntry {
+        len,
+        modified,
+        chunk_types: BTreeSet::new(),
+        keywords: BTreeSet::new(),
+        magics: BTreeSet::new(),
+    };
+    for chunk in png.chunks() {
+        let chunk_type = chunk.chunk_type().to_string();
+        if matches!(chunk_type.as_str(), "tEXt" | "zTXt" | "iTXt") {
+            let keyword = chunk.data().split(|&b| b == 0).next().unwrap_or_default();
+            // Keywords are Latin-1.
+            entry
+                .keywords
+                .insert(keyword.iter().map(|&b| b as char).collect());
+        }
+        if !chunk.chunk_type().is_critical() {
+            if let Some(name) = magic::detect(chunk.data()) {
+                entry.magics.insert(name.to_string());
+            }
+        }
+        entry.chunk_types.insert(chunk_type);
+    }
+    Ok(entry)
+}
@@ -0,0 +1,51 @@
+use serde::Serialize;
+
+use pngme::ihdr::Ihdr;
+use pngme::Result;
+
+use super::{describe_size, print_json, read_png};
+use crate::args::{GlobalArgs, InfoArgs, OutputFormat};
+
+#[derive(Serialize)]
+struct Info {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: &'static str,
+    interlaced: bool,
+    idat_chunks: usize,
+    file_size: u64,
+    transparency: bool,
+    palette: bool,
+}
+
+pub fn info(args: InfoArgs, global: &GlobalArgs) -> Result<()> {
+    let png = read_png(&args.file_path)?;
+    let ihdr = Ihdr::from_png(&png)?;
+    let info = Info {
+        width: ihdr.width,
+        height: ihdr.height,
+        bit_depth: ihdr.bit_depth,
+        color_type: ihdr.color_type_name(),
+        interlaced: ihdr.is_interlaced(),
+        idat_chunks: png.chunks_by_type("IDAT").count(),
+        file_size: png.byte_len() as u64,
+        transparency: ihdr.has_alpha() || png.chunk_by_type("tRNS").is_some(),
+        palette: png.chunk_by_type("PLTE").is_some(),
+    };
+
+    if global.format == OutputFormat::Json {
+        return print_json(&info);
+    }
+
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    println!("Dimensions:   {} x {}", info.width, info.height);
+    println!("Bit depth:    {}", info.bit_depth);
+    println!("Color type:   {}", info.color_type);
+    println!("Interlaced:   {}", yes_no(info.interlaced));
+    println!("IDAT chunks:  {}", info.idat_chunks);
+    println!("File size:    {}", describe_size(info.file_size, global));
+    println!("Transparency: {}", yes_no(info.transparency));
+    println!("Palette:      {}", yes_no(info.palette));
+    Ok(())
+}
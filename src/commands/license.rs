@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use pngme::{text::TextChunk, Result};
+
+use super::png_files;
+use crate::args::LicenseScanArgs;
+
+const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+
+#[derive(Default)]
+struct Attribution {
+    authors: Vec<String>,
+    copyrights: Vec<String>,
+    licenses: Vec<String>,
+}
+
+pub fn license_scan(args: LicenseScanArgs) -> Result<()> {
+    let mut files: Vec<(PathBuf, Attribution)> = vec![];
+    for path in png_files(&args.dir) {
+        match attribution(&path) {
+            Ok(attribution) => files.push((path, attribution)),
+            Err(e) => eprintln!("Skipping {}: {}", path.display(), e),
+        }
+    }
+
+    let mut by_license: BTreeMap<&str, Vec<&Path>> = BTreeMap::new();
+    for (path, attribution) in &files {
+        for license in &attribution.licenses {
+            by_license.entry(license).or_default().push(path);
+        }
+    }
+
+    println!("== Attribution ==");
+    for (path, attribution) in &files {
+        if attribution.authors.is_empty()
+            && attribution.copyrights.is_empty()
+            && attribution.licenses.is_empty()
+        {
+            continue;
+        }
+        println!("{}", path.display());
+        for author in &attribution.authors {
+            println!("    Author: {}", author);
+        }
+        for copyright in &attribution.copyrights {
+            println!("    Copyright: {}", copyright);
+        }
+        for license in &attribution.licenses {
+            println!("    License: {}", license);
+        }
+    }
+
+    println!();
+    println!("== Licenses ==");
+    for (license, paths) in &by_license {
+        println!("{} ({} files)", license, paths.len());
+    }
+
+    let missing: Vec<&PathBuf> = files
+        .iter()
+        .filter(|(_, attribution)| attribution.licenses.is_empty())
+        .map(|(path, _)| path)
+        .collect();
+    println!();
+    println!("== Missing license metadata ({} files) ==", missing.len());
+    for path in missing {
+        println!("{}", path.display());
+    }
+
+    Ok(())
+}
+
+fn attribution(path: &Path) -> Result<Attribution> {
+    let png = super::read_png(path)?;
+    let mut attribution = Attribution::default();
+    for chunk in png.chunks() {
+        let Some(text) = TextChunk::parse(chunk) else {
+            continue;
+        };
+        let text = text?;
+        let value = text.text.trim().to_string();
+        match text.keyword.as_str() {
+            "Author" | "Artist" => attribution.authors.push(value),
+            "Copyright" => attribution.copyrights.push(value),
+            "License" => attribution.licenses.push(value),
+            XMP_KEYWORD => {
+                attribution
+                    .authors
+                    .extend(xmp_element(&value, "dc:creator"));
+                attribution
+                    .copyrights
+                    .extend(xmp_element(&value, "dc:rights"));
+                attribution
+                    .licenses
+                    .extend(xmp_element(&value, "cc:license"));
+                attribution
+                    .licenses
+                    .extend(xmp_attribute(&value, "cc:license", "rdf:resource"));
+                attribution
+                    .licenses
+                    .extend(xmp_element(&value, "xmpRights:WebStatement"));
+                attribution.licenses.extend(xmp_attribute(
+                    &value,
+                    "rdf:Description",
+                    "xmpRights:WebStatement",
+                ));
+            }
+            _ => {}
+        }
+    }
+    for values in [
+        &mut attribution.authors,
+        &mut attribution.copyrights,
+        &mut attribution.licenses,
+    ] {
+        values.retain(|v| !v.is_empty());
+        values.dedup();
+    }
+    Ok(attribution)
+}
+
+/// Text content of `<tag>...</tag>`, with nested markup such as `rdf:Alt`
+/// and `rdf:li` stripped.
+fn xmp_element(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}", tag);
+    let start = xml.find(&open)?;
+    let content_start = start + xml[start..].find('>')? + 1;
+    if xml[..content_start].ends_with("/>") {
+        return None;
+    }
+    let close = format!("</{}>", tag);
+    let content_end = content_start + xml[content_start..].find(&close)?;
+
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in xml[content_start..content_end].chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                text.push(' ');
+            }
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    Some(text.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Value of `attribute="..."` on the first `<tag ...>` element.
+fn xmp_attribute(xml: &str, tag: &str, attribute: &str) -> Option<String> {
+    let start = xml.find(&format!("<{}", tag))?;
+    let element = &xml[start..start + xml[start..].find('>')?];
+    let prefix = format!("{}=\"", attribute);
+    let value_start = element.find(&prefix)? + prefix.len();
+    let value_len = element[value_start..].find('"')?;
+    Some(element[value_start..value_start + value_len].to_string())
+}
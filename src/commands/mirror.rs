@@ -0,0 +1,87 @@
+use std::path::Path;
+
+use pngme::{similarity, Chunk, Png, Result};
+
+use super::write_png;
+use crate::args::{GlobalArgs, MirrorArgs, OutputArgs};
+
+/// Text chunks carrying license and provenance metadata, mirrored by default
+/// alongside every private chunk.
+const TEXT_CHUNKS: [&str; 3] = ["tEXt", "zTXt", "iTXt"];
+
+pub fn mirror(args: MirrorArgs, global: &GlobalArgs) -> Result<()> {
+    let master = super::read_png(&args.master)?;
+    let master_hash = similarity::average_hash(&master)?;
+    let chunks: Vec<&Chunk> = master
+        .chunks()
+        .iter()
+        .filter(|c| is_mirrored(c, &args.chunk_types))
+        .collect();
+
+    let mut skipped = 0;
+    for path in &args.derived {
+        match mirror_to(path, master_hash, &chunks, &args, global) {
+            Ok(()) => println!("{}: mirrored {} chunks", path.display(), chunks.len()),
+            Err(e) => {
+                eprintln!("Skipping {}: {}", path.display(), e);
+                skipped += 1;
+            }
+        }
+    }
+
+    if skipped > 0 {
+        return Err(format!("{} of {} files skipped", skipped, args.derived.len()).into());
+    }
+    Ok(())
+}
+
+/// Replaces the mirrored chunks of the image at `path` with `chunks`, after
+/// checking that its perceptual hash is close to the master's.
+fn mirror_to(
+    path: &Path,
+    master_hash: u64,
+    chunks: &[&Chunk],
+    args: &MirrorArgs,
+    global: &GlobalArgs,
+) -> Result<()> {
+    let png = super::read_png(path)?;
+    let distance = similarity::distance(master_hash, similarity::average_hash(&png)?);
+    if distance > args.max_distance && !args.force {
+        return Err(format!(
+            "does not look derived from the master ({} of 64 hash bits differ)",
+            distance
+        )
+        .into());
+    }
+
+    let kept = png
+        .chunks()
+        .iter()
+        .filter(|c| !is_mirrored(c, &args.chunk_types))
+        .cloned()
+        .collect();
+    let mut png = Png::from_chunks(kept);
+    let end = *png.insertion_points().last().unwrap();
+    for (offset, chunk) in chunks.iter().enumerate() {
+        png.insert_chunk_at(end + offset, (*chunk).clone());
+    }
+
+    let output = OutputArgs {
+        path: None,
+        trash: false,
+        backup: false,
+        force: false,
+        emit: None,
+    };
+    write_png(path, &png, &output, global)
+}
+
+/// Whether `chunk` is one of `chunk_types`, or with none given, a text or
+/// private chunk.
+fn is_mirrored(chunk: &Chunk, chunk_types: &[String]) -> bool {
+    let chunk_type = chunk.chunk_type().to_string();
+    if !chunk_types.is_empty() {
+        return chunk_types.contains(&chunk_type);
+    }
+    TEXT_CHUNKS.contains(&chunk_type.as_str()) || !chunk.chunk_type().is_public()
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use pngme::{template, Result};
+
+/// Expands `{env:NAME}`, `{date}`, `{file.stem}`, `{file.name}` and
+/// `{git.sha}` in a message about to be encoded into `file_path`.
+pub fn expand(message: &str, file_path: &Path) -> Result<String> {
+    Ok(template::interpolate(message, |name| {
+        if let Some(var) = name.strip_prefix("env:") {
+            return std::env::var(var).map_err(|e| e.to_string());
+        }
+        match name {
+            "date" => Ok(today()),
+            "file.stem" => file_name(file_path, Path::file_stem),
+            "file.name" => file_name(file_path, Path::file_name),
+            "git.sha" => git_sha(file_path),
+            _ => Err("unknown placeholder".to_string()),
+        }
+    })?)
+}
+
+fn file_name(
+    path: &Path,
+    part: impl Fn(&Path) -> Option<&std::ffi::OsStr>,
+) -> std::result::Result<String, String> {
+    part(path)
+        .map(|s| s.to_string_lossy().to_string())
+        .ok_or_else(|| "no file name".to_string())
+}
+
+/// HEAD of the git repository containing `path`.
+fn git_sha(path: &Path) -> std::result::Result<String, String> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Today's UTC date as YYYY-MM-DD.
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian date, following
+/// Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,70 @@
+use serde::Serialize;
+
+use pngme::{stream::ChunkReader, ChunkError, ChunkType, Result};
+
+use super::{format_size, print_json};
+use crate::args::{GlobalArgs, OutputFormat, ScanArgs};
+use crate::storage;
+
+#[derive(Serialize)]
+struct ScanEntry {
+    index: usize,
+    offset: u64,
+    #[serde(rename = "type")]
+    chunk_type: ChunkType,
+    length: u32,
+    crc_ok: bool,
+    description: Option<&'static str>,
+    suspicious: bool,
+}
+
+pub fn scan(args: ScanArgs, global: &GlobalArgs) -> Result<()> {
+    let mut reader = ChunkReader::new(storage::open(&args.file_path)?)?;
+
+    let mut entries = vec![];
+    while let Some(header) = reader.next_header()? {
+        let crc_ok = match reader.copy_data(&mut std::io::sink()) {
+            Ok(()) => true,
+            Err(e) if matches!(e.downcast_ref(), Some(ChunkError::CrcMismatch)) => false,
+            Err(e) => return Err(e),
+        };
+        let chunk_type = header.chunk_type;
+        entries.push(ScanEntry {
+            index: entries.len(),
+            offset: header.offset,
+            length: header.length,
+            crc_ok,
+            description: chunk_type.known_description(),
+            suspicious: !chunk_type.is_standard() && !chunk_type.is_critical(),
+            chunk_type,
+        });
+    }
+
+    if global.format == OutputFormat::Json {
+        return print_json(&entries);
+    }
+
+    println!(
+        "{:>4} {:>10} {:<4} {:>10} {:<4}  notes",
+        "idx", "offset", "type", "length", "crc"
+    );
+    for entry in &entries {
+        let notes = match entry.description {
+            Some(description) => description,
+            None if entry.suspicious => "suspicious: non-standard ancillary chunk",
+            None => "unknown critical chunk",
+        };
+        println!(
+            "{:>4} {:>10} {:<4} {:>10} {:<4}  {}",
+            entry.index,
+            entry.offset,
+            entry.chunk_type,
+            format_size(entry.length as u64, global),
+            if entry.crc_ok { "ok" } else { "BAD" },
+            notes
+        );
+    }
+    let suspicious = entries.iter().filter(|e| e.suspicious).count();
+    println!("{} chunks, {} suspicious", entries.len(), suspicious);
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use std::io::Write;
+
+use pngme::{crypto, stego, Result};
+
+use super::{describe_size, passphrase, read_message, read_png, write_png};
+use crate::args::{GlobalArgs, StdinFormat, StegoDecodeArgs, StegoEncodeArgs};
+
+pub fn stego_encode(args: StegoEncodeArgs, global: &GlobalArgs) -> Result<()> {
+    let png = read_png(&args.file_path)?;
+    let capacity = stego::capacity(&png)?;
+    if args.capacity {
+        println!(
+            "{} can hide {}",
+            args.file_path.display(),
+            describe_size(capacity as u64, global)
+        );
+        return Ok(());
+    }
+
+    let mut message = read_message(args.message, StdinFormat::Raw)?;
+    if args.encrypt {
+        message = crypto::encrypt(&message, &passphrase(true)?)?;
+    }
+    let png = stego::embed(&png, &message)?;
+    write_png(&args.file_path, &png, &args.output, global)?;
+    eprintln!(
+        "Hid {} of {} capacity",
+        describe_size(message.len() as u64, global),
+        describe_size(capacity as u64, global)
+    );
+    Ok(())
+}
+
+pub fn stego_decode(args: StegoDecodeArgs) -> Result<()> {
+    let png = read_png(&args.file_path)?;
+    let mut message = stego::extract(&png)?;
+    if args.decrypt {
+        message = crypto::decrypt(&message, &passphrase(false)?)?;
+    }
+    std::io::stdout().write_all(&message)?;
+    println!();
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use pngme::store::MessageStore;
+use pngme::{PngMeError, Result};
+
+use super::{format_size, print_json, read_png};
+use crate::args::{GlobalArgs, KeysArgs, OutputFormat};
+
+#[derive(serde::Serialize)]
+struct Entry<'a> {
+    key: &'a str,
+    length: usize,
+}
+
+pub fn keys(args: KeysArgs, global: &GlobalArgs) -> Result<()> {
+    let png = read_png(&args.file_path)?;
+    let store = MessageStore::load(&png)?;
+    let entries: Vec<Entry> = store
+        .iter()
+        .map(|(key, value)| Entry {
+            key,
+            length: value.len(),
+        })
+        .collect();
+
+    if global.format == OutputFormat::Json {
+        return print_json(&entries);
+    }
+    for entry in &entries {
+        println!(
+            "{:>10} {}",
+            format_size(entry.length as u64, global),
+            entry.key
+        );
+    }
+    Ok(())
+}
+
+pub fn no_entry(key: &str) -> pngme::Error {
+    PngMeError::ChunkNotFound(format!("no entry {}", key).into()).into()
+}
@@ -0,0 +1,158 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use pngme::{Chunk, ChunkType, Result};
+
+use super::batch::{self, Outcome};
+use super::{describe_size, display_chunk, read_png, write_png, writes_to_stdout};
+use crate::args::{GlobalArgs, StripArgs};
+
+pub fn strip(args: StripArgs, global: &GlobalArgs) -> Result<()> {
+    let selection = Selection::new(&args)?;
+
+    if let Some(paths) = batch::expand(&args.file_path, args.recursive)? {
+        batch::check_output(&args.output)?;
+        return batch::run(paths, |path| {
+            Ok(match strip_file(path, &args, &selection, global)?.len() {
+                0 => Outcome::Skipped("nothing to strip".to_string()),
+                _ => Outcome::Modified,
+            })
+        });
+    }
+
+    let removed = strip_file(&args.file_path, &args, &selection, global)?;
+    for chunk in &removed {
+        eprintln!("Removed: {}", display_chunk(chunk, global));
+    }
+    let size: u64 = removed.iter().map(|c| c.length() as u64 + 12).sum();
+    eprintln!(
+        "Stripped {} chunks, {}",
+        removed.len(),
+        describe_size(size, global)
+    );
+    Ok(())
+}
+
+/// Strips the file at `path` and returns the removed chunks. The file is only
+/// rewritten when something was removed, but a PNG for stdout is always
+/// written.
+fn strip_file(
+    path: &Path,
+    args: &StripArgs,
+    selection: &Selection,
+    global: &GlobalArgs,
+) -> Result<Vec<Chunk>> {
+    let mut png = read_png(path)?;
+    let removed = png.retain_chunks(|chunk| selection.is_kept(chunk.chunk_type()));
+    if !removed.is_empty() || writes_to_stdout(path, &args.output) {
+        write_png(path, &png, &args.output, global)?;
+    }
+    Ok(removed)
+}
+
+/// The chunk types named by `--keep` and `--drop`.
+struct Selection {
+    keep: Vec<ChunkType>,
+    drop: Vec<ChunkType>,
+    drop_private: bool,
+}
+
+impl Selection {
+    /// Parses the chunk types of `args`, none of which may be critical.
+    fn new(args: &StripArgs) -> Result<Selection> {
+        let parse = |types: &[String]| {
+            types
+                .iter()
+                .map(|t| {
+                    let chunk_type = ChunkType::from_str(t)?;
+                    if chunk_type.is_critical() {
+                        return Err(format!("{} is a critical chunk and is always kept", t).into());
+                    }
+                    Ok(chunk_type)
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Selection {
+            keep: parse(&args.keep)?,
+            drop: parse(&args.drop)?,
+            drop_private: args.drop_private,
+        })
+    }
+
+    /// Whether a chunk of `chunk_type` survives: critical chunks always do,
+    /// and ancillary ones when kept by `--keep`, or not named by `--drop`.
+    fn is_kept(&self, chunk_type: &ChunkType) -> bool {
+        if chunk_type.is_critical() {
+            return true;
+        }
+        if self.drop.is_empty() {
+            return self.keep.contains(chunk_type);
+        }
+        !self.drop.contains(chunk_type) && (chunk_type.is_public() || !self.drop_private)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    use crate::args::{Cli, Command};
+
+    fn strip_args(flags: &[&str]) -> Selection {
+        let args = ["pngme", "strip", "in.png"].iter().chain(flags);
+        match Cli::parse_from(args).command {
+            Command::Strip(args) => Selection::new(&args).unwrap(),
+            _ => unreachable!(),
+        }
+    }
+
+    fn kept(chunk_type: &str, selection: &Selection) -> bool {
+        selection.is_kept(&ChunkType::from_str(chunk_type).unwrap())
+    }
+
+    #[test]
+    fn test_strips_every_ancillary_chunk() {
+        let args = strip_args(&[]);
+        assert!(!kept("tEXt", &args));
+        assert!(!kept("ruSt", &args));
+    }
+
+    #[test]
+    fn test_keep() {
+        let args = strip_args(&["--keep", "tRNS", "--keep", "gAMA"]);
+        assert!(kept("tRNS", &args));
+        assert!(kept("gAMA", &args));
+        assert!(!kept("tEXt", &args));
+    }
+
+    #[test]
+    fn test_drop() {
+        let args = strip_args(&["--drop", "tEXt"]);
+        assert!(!kept("tEXt", &args));
+        assert!(kept("zTXt", &args));
+        assert!(kept("ruSt", &args));
+    }
+
+    #[test]
+    fn test_drop_private() {
+        let args = strip_args(&["--drop", "tEXt", "--drop-private"]);
+        assert!(!kept("tEXt", &args));
+        assert!(!kept("ruSt", &args));
+        assert!(kept("zTXt", &args));
+    }
+
+    #[test]
+    fn test_critical_chunks_survive() {
+        for flags in [
+            &[][..],
+            &["--keep", "tEXt"],
+            &["--drop", "tEXt", "--drop-private"],
+        ] {
+            let args = strip_args(flags);
+            for chunk_type in ["IHDR", "PLTE", "IDAT", "IEND"] {
+                assert!(kept(chunk_type, &args), "{} with {:?}", chunk_type, flags);
+            }
+        }
+    }
+}
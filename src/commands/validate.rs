@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use pngme::{png::ParseIssue, zlib, Png, PngMeError, Result};
+
+use super::{describe_size, png_files};
+use crate::args::{GlobalArgs, OutputFormat, ReportFormat, ValidateArgs};
+use crate::storage;
+
+struct FileReport {
+    path: PathBuf,
+    /// One line per check, `Err` for the ones that found a problem.
+    checks: Vec<std::result::Result<String, String>>,
+    elapsed: Duration,
+}
+
+impl FileReport {
+    fn problems(&self) -> Vec<&str> {
+        self.checks
+            .iter()
+            .filter_map(|c| c.as_ref().err().map(|e| e.as_str()))
+            .collect()
+    }
+}
+
+pub fn validate(args: ValidateArgs, global: &GlobalArgs) -> Result<()> {
+    let mut paths = vec![];
+    for path in args.paths {
+        if path.is_dir() {
+            paths.extend(png_files(&path));
+        } else {
+            paths.push(path);
+        }
+    }
+
+    let reports: Vec<FileReport> = paths
+        .into_iter()
+        .map(|path| validate_file(path, args.fix, global))
+        .collect();
+    match (global.format, args.report) {
+        (OutputFormat::Json, _) => print_json(&reports)?,
+        (_, ReportFormat::Text) => print_text(&reports),
+        (_, ReportFormat::Junit) => print_junit(&reports),
+    }
+
+    let failed = reports.iter().filter(|r| !r.problems().is_empty()).count();
+    if failed > 0 {
+        let message = format!("{} of {} files failed validation", failed, reports.len());
+        return Err(PngMeError::Parse(message.into()).into());
+    }
+    Ok(())
+}
+
+fn validate_file(path: PathBuf, fix: bool, global: &GlobalArgs) -> FileReport {
+    let start = Instant::now();
+    let mut report = FileReport {
+        path,
+        checks: vec![],
+        elapsed: Duration::ZERO,
+    };
+
+    let parsed = storage::read(&report.path).and_then(|bytes| Ok(Png::parse_lenient(&bytes)?));
+    match parsed {
+        Ok((png, issues)) => {
+            let fixed = fix && issues.iter().any(ParseIssue::is_fixable);
+            if issues.is_empty() {
+                report
+                    .checks
+                    .push(Ok(format!("chunks: ok ({} chunks)", png.chunks().len())));
+            }
+            for issue in &issues {
+                report.checks.push(if fixed && issue.is_fixable() {
+                    Ok(format!("{} (fixed)", issue))
+                } else {
+                    Err(issue.to_string())
+                });
+            }
+            if fixed {
+                if let Err(e) = storage::write(&report.path, &png.as_bytes()) {
+                    report.checks.push(Err(format!("fix: {}", e)));
+                }
+            }
+
+            for stream in zlib::verify_streams(&png) {
+                report.checks.push(match stream.result {
+                    Ok(len) => Ok(format!(
+                        "{}: ok ({} inflated)",
+                        stream.label,
+                        describe_size(len as u64, global)
+                    )),
+                    Err(e) => Err(format!("{}: {}", stream.label, e)),
+                });
+            }
+        }
+        Err(e) => report.checks.push(Err(e.to_string())),
+    }
+
+    report.elapsed = start.elapsed();
+    report
+}
+
+fn print_json(reports: &[FileReport]) -> Result<()> {
+    #[derive(Serialize)]
+    struct Check<'a> {
+        ok: bool,
+        message: &'a str,
+    }
+    #[derive(Serialize)]
+    struct File<'a> {
+        path: String,
+        ok: bool,
+        checks: Vec<Check<'a>>,
+        seconds: f64,
+    }
+
+    let files: Vec<File> = reports
+        .iter()
+        .map(|report| File {
+            path: report.path.display().to_string(),
+            ok: report.problems().is_empty(),
+            checks: report
+                .checks
+                .iter()
+                .map(|check| match check {
+                    Ok(message) => Check { ok: true, message },
+                    Err(message) => Check { ok: false, message },
+                })
+                .collect(),
+            seconds: report.elapsed.as_secs_f64(),
+        })
+        .collect();
+    super::print_json(&files)
+}
+
+fn print_text(reports: &[FileReport]) {
+    let many = reports.len() > 1;
+    for report in reports {
+        if many {
+            println!("{}:", report.path.display());
+        }
+        let indent = if many { "    " } else { "" };
+        for check in &report.checks {
+            match check {
+                Ok(line) | Err(line) => println!("{}{}", indent, line),
+            }
+        }
+    }
+}
+
+fn print_junit(reports: &[FileReport]) {
+    let failures = reports.iter().filter(|r| !r.problems().is_empty()).count();
+    let total: Duration = reports.iter().map(|r| r.elapsed).sum();
+
+    println!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    println!(
+        "<testsuite name=\"pngme validate\" tests=\"{}\" failures=\"{}\" errors=\"0\" time=\"{:.3}\">",
+        reports.len(),
+        failures,
+        total.as_secs_f64()
+    );
+    for report in reports {
+        let name = escape_xml(&report.path.display().to_string());
+        let time = report.elapsed.as_secs_f64();
+        let problems = report.problems();
+        if problems.is_empty() {
+            println!(
+                "  <testcase classname=\"pngme.validate\" name=\"{}\" time=\"{:.3}\"/>",
+                name, time
+            );
+        } else {
+            println!(
+                "  <testcase classname=\"pngme.validate\" name=\"{}\" time=\"{:.3}\">",
+                name, time
+            );
+            println!(
+                "    <failure message=\"{}\">{}</failure>",
+                escape_xml(problems[0]),
+                escape_xml(&problems.join("\n"))
+            );
+            println!("  </testcase>");
+        }
+    }
+    println!("</testsuite>");
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,128 @@
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use pngme::message::{self, DecodeOptions, EncodeOptions};
+use pngme::{ChunkType, Result};
+use rand::{rngs::StdRng, RngExt};
+
+use super::{read_png, write_png};
+use crate::args::{GlobalArgs, OutputArgs};
+
+pub fn wizard(global: &GlobalArgs) -> Result<()> {
+    eprintln!("This wizard hides a message in a PNG file. Press Ctrl-D to quit.");
+
+    let (file_path, mut png) = loop {
+        let path = PathBuf::from(prompt("PNG file: ")?);
+        match read_png(&path) {
+            Ok(png) => break (path, png),
+            Err(e) => eprintln!("Cannot read {}: {}", path.display(), e),
+        }
+    };
+
+    let chunk_type = loop {
+        let answer = prompt("Chunk type (4 letters, empty to generate one): ")?;
+        if answer.is_empty() {
+            let chunk_type = random_chunk_type();
+            eprintln!("Using chunk type {}", chunk_type);
+            break chunk_type;
+        }
+        match parse_chunk_type(&answer) {
+            Some(chunk_type) => break chunk_type,
+            None => eprintln!(
+                "Use 4 ASCII letters, the first lowercase and the third uppercase, e.g. ruSt"
+            ),
+        }
+    };
+
+    let message = loop {
+        let message = prompt("Message: ")?;
+        if !message.is_empty() {
+            break message;
+        }
+    };
+
+    let output = prompt(&format!(
+        "Write to (empty to overwrite {}): ",
+        file_path.display()
+    ))?;
+    let output = OutputArgs {
+        path: (!output.is_empty()).then(|| PathBuf::from(output)),
+        trash: false,
+        backup: false,
+        force: true,
+        emit: None,
+    };
+
+    let chunk_type_str = chunk_type.to_string();
+    let payload = message::encode_payload(message.as_bytes(), &EncodeOptions::default())?;
+    png.insert_before_iend(message::payload_chunks(chunk_type, &payload, None)?);
+    write_png(&file_path, &png, &output, global)?;
+
+    let written = output.path.as_deref().unwrap_or(&file_path);
+    verify(written, &chunk_type_str, &message)?;
+    eprintln!();
+    eprintln!("Done. Read the message back with:");
+    eprintln!("    pngme decode {} {}", written.display(), chunk_type_str);
+    Ok(())
+}
+
+/// Decodes the message back from the written file.
+fn verify(path: &Path, chunk_type: &str, message: &str) -> Result<()> {
+    let png = read_png(path)?;
+    let found = png
+        .chunks()
+        .iter()
+        .rev()
+        .find(|c| c.chunk_type().to_string() == chunk_type);
+    let decoded = found
+        .map(|chunk| {
+            message::decode_payload(&message::chunk_payload(chunk), &DecodeOptions::default())
+        })
+        .transpose()?;
+    match decoded {
+        Some(decoded) if decoded == message.as_bytes() => {
+            eprintln!(
+                "Verified: {} in {} holds the message",
+                chunk_type,
+                path.display()
+            );
+            Ok(())
+        }
+        _ => Err(format!(
+            "{} in {} does not hold the message",
+            chunk_type,
+            path.display()
+        )
+        .into()),
+    }
+}
+
+/// Prints `question` and reads one line from stdin, without the line ending.
+fn prompt(question: &str) -> Result<String> {
+    eprint!("{}", question);
+    std::io::stderr().flush()?;
+    let mut line = String::new();
+    if std::io::stdin().lock().read_line(&mut line)? == 0 {
+        return Err("aborted".into());
+    }
+    Ok(line.trim().to_string())
+}
+
+/// A chunk type that is valid and can be used to hide a message: ancillary,
+/// so decoders ignore it.
+fn parse_chunk_type(s: &str) -> Option<ChunkType> {
+    if s.len() != 4 {
+        return None;
+    }
+    let chunk_type = ChunkType::from_str(s).ok()?;
+    (chunk_type.is_valid() && !chunk_type.is_critical()).then_some(chunk_type)
+}
+
+/// A random ancillary, private, safe-to-copy chunk type such as `qzEk`.
+fn random_chunk_type() -> ChunkType {
+    let mut rng: StdRng = rand::make_rng();
+    let mut letter = || b'a' + rng.random_range(0..26u8);
+    let bytes = [letter(), letter(), letter().to_ascii_uppercase(), letter()];
+    ChunkType::try_from(bytes).unwrap()
+}
@@ -0,0 +1,194 @@
+//! Compression of chunk payloads before they are embedded. Compressed
+//! payloads start with a header naming the method, so they can be recognised
+//! and decompressed without being told how they were stored:
+//!
+//! ```text
+//! "PNGZ" | method (1) | compressed bytes
+//! ```
+//!
+//! Uncompressed payloads that happen to start with the header are stored
+//! behind it with the "stored" method, see [`escape`].
+
+use std::borrow::Cow;
+
+use crate::zlib::{self, ZlibError};
+
+const MAGIC: &[u8; 4] = b"PNGZ";
+const HEADER_LEN: usize = MAGIC.len() + 1;
+/// Method marker of payloads kept as they are.
+const STORED: u8 = 0xff;
+
+/// Largest payload [`decompress`] inflates, so that a small chunk cannot
+/// expand into gigabytes.
+pub const MAX_DECOMPRESSED_LEN: usize = 1 << 30;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CompressionError {
+    #[error("unknown compression method {0}")]
+    UnknownMethod(u8),
+    #[error(transparent)]
+    Deflate(#[from] ZlibError),
+    #[error("corrupt zstd stream: {0}")]
+    Zstd(std::io::Error),
+    #[error("zstd support is not compiled in")]
+    ZstdUnsupported,
+    #[error("decompressed payload exceeds {0} bytes")]
+    TooLarge(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    Deflate,
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Method {
+    fn marker(self) -> u8 {
+        match self {
+            Method::Deflate => 0,
+            #[cfg(feature = "zstd")]
+            Method::Zstd => 1,
+        }
+    }
+}
+
+/// Whether `data` starts with the header of a compressed payload.
+pub fn is_compressed(data: &[u8]) -> bool {
+    data.len() >= HEADER_LEN && data.starts_with(MAGIC)
+}
+
+/// Compresses `data` with `method` behind the header.
+pub fn compress(data: &[u8], method: Method) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(method.marker());
+    match method {
+        Method::Deflate => out.extend(zlib::deflate(data)),
+        // Compressing into a Vec cannot fail.
+        #[cfg(feature = "zstd")]
+        Method::Zstd => out.extend(zstd::encode_all(data, 19).unwrap()),
+    }
+    out
+}
+
+/// `data` as it should be embedded when it is not compressed: unchanged,
+/// unless it starts with the header and would be mistaken for a compressed
+/// payload. [`decompress`] returns such data as it was.
+pub fn escape(data: &[u8]) -> Cow<'_, [u8]> {
+    if !data.starts_with(MAGIC) {
+        return Cow::Borrowed(data);
+    }
+    let mut out = MAGIC.to_vec();
+    out.push(STORED);
+    out.extend(data);
+    Cow::Owned(out)
+}
+
+/// Decompresses a payload produced by [`compress`] or [`escape`], or returns
+/// `None` if `data` is not compressed. Fails rather than inflate more than
+/// [`MAX_DECOMPRESSED_LEN`] bytes.
+pub fn decompress(data: &[u8]) -> Option<std::result::Result<Vec<u8>, CompressionError>> {
+    if !is_compressed(data) {
+        return None;
+    }
+    let stream = &data[HEADER_LEN..];
+    Some(match data[MAGIC.len()] {
+        0 => zlib::inflate_limited(stream, MAX_DECOMPRESSED_LEN).map_err(|e| match e {
+            ZlibError::TooLarge(limit) => CompressionError::TooLarge(limit),
+            e => CompressionError::Deflate(e),
+        }),
+        #[cfg(feature = "zstd")]
+        1 => zstd_decode(stream, MAX_DECOMPRESSED_LEN),
+        #[cfg(not(feature = "zstd"))]
+        1 => Err(CompressionError::ZstdUnsupported),
+        STORED => Ok(stream.to_vec()),
+        other => Err(CompressionError::UnknownMethod(other)),
+    })
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_decode(stream: &[u8], limit: usize) -> std::result::Result<Vec<u8>, CompressionError> {
+    use std::io::Read;
+
+    let mut out = vec![];
+    zstd::stream::read::Decoder::new(stream)
+        .and_then(|decoder| decoder.take(limit as u64 + 1).read_to_end(&mut out))
+        .map_err(CompressionError::Zstd)?;
+    if out.len() > limit {
+        return Err(CompressionError::TooLarge(limit));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let text = "A secret message! ".repeat(100);
+        let methods = [
+            Method::Deflate,
+            #[cfg(feature = "zstd")]
+            Method::Zstd,
+        ];
+        for method in methods {
+            let data = compress(text.as_bytes(), method);
+            assert!(is_compressed(&data));
+            assert!(data.len() < text.len() / 4);
+            assert_eq!(decompress(&data).unwrap().unwrap(), text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_escape() {
+        assert!(matches!(escape(b"hello"), Cow::Borrowed(b"hello")));
+        for plain in [&b"PNGZ!hello"[..], b"PNGZ", b"PNGZ\x00not deflate"] {
+            let escaped = escape(plain);
+            assert!(is_compressed(&escaped));
+            assert_eq!(decompress(&escaped).unwrap().unwrap(), plain);
+        }
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let zeros = vec![0; 4096];
+        let data = compress(&zeros, Method::Deflate);
+        assert!(matches!(
+            zlib::inflate_limited(&data[HEADER_LEN..], 4095),
+            Err(ZlibError::TooLarge(4095))
+        ));
+        assert_eq!(
+            zlib::inflate_limited(&data[HEADER_LEN..], 4096).unwrap(),
+            zeros
+        );
+        #[cfg(feature = "zstd")]
+        {
+            let data = compress(&zeros, Method::Zstd);
+            assert!(matches!(
+                zstd_decode(&data[HEADER_LEN..], 4095),
+                Err(CompressionError::TooLarge(4095))
+            ));
+            assert_eq!(zstd_decode(&data[HEADER_LEN..], 4096).unwrap(), zeros);
+        }
+    }
+
+    #[test]
+    fn test_not_compressed_or_corrupt() {
+        assert!(decompress(b"A secret message!").is_none());
+        assert!(matches!(
+            decompress(b"PNGZ\x07data"),
+            Some(Err(CompressionError::UnknownMethod(7)))
+        ));
+        #[cfg(feature = "zstd")]
+        assert!(matches!(
+            decompress(b"PNGZ\x01data"),
+            Some(Err(CompressionError::Zstd(_)))
+        ));
+        #[cfg(not(feature = "zstd"))]
+        assert!(matches!(
+            decompress(b"PNGZ\x01data"),
+            Some(Err(CompressionError::ZstdUnsupported))
+        ));
+    }
+}
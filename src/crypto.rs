@@ -0,0 +1,115 @@
+//! Passphrase encryption of chunk payloads. The key is derived with Argon2id
+//! and the payload sealed with ChaCha20-Poly1305. Encrypted payloads start
+//! with a header so they can be recognised and decrypted without any other
+//! information than the passphrase:
+//!
+//! ```text
+//! "PNGME" | version (1) | salt (16) | nonce (12) | ciphertext and tag
+//! ```
+
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Nonce,
+};
+use rand::{rngs::StdRng, RngExt};
+
+const MAGIC: &[u8; 5] = b"PNGME";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CryptoError {
+    #[error("payload is not encrypted")]
+    NotEncrypted,
+    #[error("unsupported encryption version {0}")]
+    UnsupportedVersion(u8),
+    #[error("payload is too large to encrypt")]
+    Encryption,
+    #[error("key derivation failed: {0}")]
+    KeyDerivation(String),
+    #[error("wrong passphrase or tampered payload")]
+    Decryption,
+}
+
+/// Whether `data` starts with the header of an encrypted payload.
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.len() >= HEADER_LEN && data.starts_with(MAGIC)
+}
+
+/// Encrypts `plaintext` under `passphrase` with a fresh salt and nonce.
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> std::result::Result<Vec<u8>, CryptoError> {
+    let mut rng: StdRng = rand::make_rng();
+    let salt: [u8; SALT_LEN] = rng.random();
+    let nonce: [u8; NONCE_LEN] = rng.random();
+
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(&Nonce::from(nonce), plaintext)
+        .map_err(|_| CryptoError::Encryption)?;
+
+    let mut data = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    data.extend(MAGIC);
+    data.push(VERSION);
+    data.extend(salt);
+    data.extend(nonce);
+    data.extend(ciphertext);
+    Ok(data)
+}
+
+/// Checks and decrypts a payload produced by [`encrypt`].
+pub fn decrypt(data: &[u8], passphrase: &str) -> std::result::Result<Vec<u8>, CryptoError> {
+    if !is_encrypted(data) {
+        return Err(CryptoError::NotEncrypted);
+    }
+    let version = data[MAGIC.len()];
+    if version != VERSION {
+        return Err(CryptoError::UnsupportedVersion(version));
+    }
+
+    let (salt, rest) = data[MAGIC.len() + 1..].split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().unwrap();
+    cipher(passphrase, salt)?
+        .decrypt(&Nonce::from(nonce), ciphertext)
+        .map_err(|_| CryptoError::Decryption)
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> std::result::Result<ChaCha20Poly1305, CryptoError> {
+    let mut key = [0; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| CryptoError::KeyDerivation(e.to_string()))?;
+    Ok(ChaCha20Poly1305::new(&key.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let data = encrypt(b"A secret message!", "hunter2").unwrap();
+        assert!(is_encrypted(&data));
+        assert_eq!(decrypt(&data, "hunter2").unwrap(), b"A secret message!");
+        assert!(matches!(
+            decrypt(&data, "hunter3"),
+            Err(CryptoError::Decryption)
+        ));
+    }
+
+    #[test]
+    fn test_tampered_or_plain() {
+        let mut data = encrypt(b"A secret message!", "hunter2").unwrap();
+        *data.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            decrypt(&data, "hunter2"),
+            Err(CryptoError::Decryption)
+        ));
+        assert!(matches!(
+            decrypt(b"A secret message!", "hunter2"),
+            Err(CryptoError::NotEncrypted)
+        ));
+    }
+}
@@ -0,0 +1,275 @@
+//! Differences between the chunk sequences of two files. Chunks present in
+//! both are matched up by a longest common subsequence; of the rest, a removed
+//! and an added chunk of the same type are reported as one modified chunk,
+//! even if it moved.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+
+/// Largest number of cells of the longest common subsequence table. Past it,
+/// the chunks between the common start and end of the files are reported as
+/// removed and added rather than aligned.
+const MAX_TABLE_CELLS: usize = 1 << 22;
+
+/// A chunk and the offset of its length field in its file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Located<'a> {
+    pub offset: u64,
+    pub chunk: &'a Chunk,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change<'a> {
+    Added(Located<'a>),
+    Removed(Located<'a>),
+    Modified { old: Located<'a>, new: Located<'a> },
+}
+
+/// The changes turning `old` into `new`, in file order. Identical files have
+/// none.
+pub fn diff<'a>(old: &'a Png, new: &'a Png) -> Vec<Change<'a>> {
+    let old = located(old);
+    let new = located(new);
+    // Type, length and CRC tell most chunks apart without comparing data.
+    let key = |l: &Located| (*l.chunk.chunk_type(), l.chunk.length(), l.chunk.crc());
+    let old_keys: Vec<(ChunkType, u32, u32)> = old.iter().map(key).collect();
+    let new_keys: Vec<(ChunkType, u32, u32)> = new.iter().map(key).collect();
+    let same = |i: usize, j: usize| {
+        old_keys[i] == new_keys[j] && old[i].chunk.data() == new[j].chunk.data()
+    };
+
+    // Edits usually leave most of the file alone, so only the middle where
+    // the files differ needs aligning.
+    let shorter = old.len().min(new.len());
+    let prefix = (0..shorter).take_while(|&k| same(k, k)).count();
+    let suffix = (0..shorter - prefix)
+        .take_while(|&k| same(old.len() - 1 - k, new.len() - 1 - k))
+        .count();
+    let (n, m) = (old.len() - prefix - suffix, new.len() - prefix - suffix);
+    let same = |i: usize, j: usize| same(prefix + i, prefix + j);
+
+    let mut changes = vec![];
+    let cells = (n + 1).checked_mul(m + 1).filter(|&c| c <= MAX_TABLE_CELLS);
+    let Some(cells) = cells else {
+        changes.extend(old[prefix..prefix + n].iter().copied().map(Change::Removed));
+        changes.extend(new[prefix..prefix + m].iter().copied().map(Change::Added));
+        return pair_up(changes);
+    };
+
+    // lcs[i * (m + 1) + j] is the length of the longest common subsequence
+    // of the middle of old from i and the middle of new from j.
+    let mut lcs = vec![0u32; cells];
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[at(i, j)] = match same(i, j) {
+                true => lcs[at(i + 1, j + 1)] + 1,
+                false => lcs[at(i + 1, j)].max(lcs[at(i, j + 1)]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && same(i, j) {
+            i += 1;
+            j += 1;
+        } else if j == m || (i < n && lcs[at(i + 1, j)] >= lcs[at(i, j + 1)]) {
+            changes.push(Change::Removed(old[prefix + i]));
+            i += 1;
+        } else {
+            changes.push(Change::Added(new[prefix + j]));
+            j += 1;
+        }
+    }
+    pair_up(changes)
+}
+
+/// Rows of 16 bytes, as offset and hex, where `old` and `new` differ: a `-`
+/// row for `old` followed by a `+` row for `new`.
+pub fn hex_diff(old: &[u8], new: &[u8]) -> Vec<String> {
+    fn row(data: &[u8], start: usize) -> Option<&[u8]> {
+        data.get(start..data.len().min(start + 16))
+            .filter(|row| !row.is_empty())
+    }
+
+    let mut lines = vec![];
+    for start in (0..old.len().max(new.len())).step_by(16) {
+        let (a, b) = (row(old, start), row(new, start));
+        if a == b {
+            continue;
+        }
+        for (sign, bytes) in [('-', a), ('+', b)] {
+            if let Some(bytes) = bytes {
+                let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                lines.push(format!("{}{:08x}  {}", sign, start, hex.join(" ")));
+            }
+        }
+    }
+    lines
+}
+
+fn located(png: &Png) -> Vec<Located<'_>> {
+    png.chunks_with_offsets()
+        .map(|(offset, chunk)| Located { offset, chunk })
+        .collect()
+}
+
+/// Turns each removed chunk and the first added chunk of the same type into
+/// one modified chunk, wherever the two are.
+fn pair_up(changes: Vec<Change>) -> Vec<Change> {
+    // Indices of the added chunks of each type, in order.
+    let mut added: HashMap<ChunkType, VecDeque<usize>> = HashMap::new();
+    for (idx, change) in changes.iter().enumerate() {
+        if let Change::Added(new) = change {
+            added
+                .entry(*new.chunk.chunk_type())
+                .or_default()
+                .push_back(idx);
+        }
+    }
+
+    let mut changes: Vec<Option<Change>> = changes.into_iter().map(Some).collect();
+    for idx in 0..changes.len() {
+        let Some(Change::Removed(old)) = changes[idx] else {
+            continue;
+        };
+        let same_type = added
+            .get_mut(old.chunk.chunk_type())
+            .and_then(VecDeque::pop_front);
+        if let Some(Some(Change::Added(new))) = same_type.map(|new| changes[new].take()) {
+            changes[idx] = Some(Change::Modified { old, new });
+        }
+    }
+    changes.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use crate::chunk_type::ChunkType;
+
+    fn chunk(chunk_type: &str, data: &str) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.into())
+    }
+
+    fn png(chunks: &[(&str, &str)]) -> Png {
+        Png::from_chunks(chunks.iter().map(|(t, d)| chunk(t, d)).collect())
+    }
+
+    fn summary(changes: &[Change]) -> Vec<String> {
+        changes
+            .iter()
+            .map(|change| match change {
+                Change::Added(new) => format!("+{}@{}", new.chunk.chunk_type(), new.offset),
+                Change::Removed(old) => format!("-{}@{}", old.chunk.chunk_type(), old.offset),
+                Change::Modified { old, new } => {
+                    format!("~{}@{}>{}", old.chunk.chunk_type(), old.offset, new.offset)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identical() {
+        let a = png(&[("IHDR", "head"), ("IDAT", "pixels"), ("IEND", "")]);
+        let b = png(&[("IHDR", "head"), ("IDAT", "pixels"), ("IEND", "")]);
+        assert!(diff(&a, &b).is_empty());
+    }
+
+    #[test]
+    fn test_added_removed_modified() {
+        let a = png(&[
+            ("IHDR", "head"),
+            ("tEXt", "old"),
+            ("IDAT", "pixels"),
+            ("IEND", ""),
+        ]);
+        let b = png(&[
+            ("IHDR", "head"),
+            ("IDAT", "pixels"),
+            ("ruSt", "secret"),
+            ("IEND", ""),
+        ]);
+        assert_eq!(summary(&diff(&a, &b)), ["-tEXt@24", "+ruSt@42"]);
+
+        let c = png(&[
+            ("IHDR", "head"),
+            ("tEXt", "new!"),
+            ("IDAT", "pixels"),
+            ("IEND", ""),
+        ]);
+        assert_eq!(summary(&diff(&a, &c)), ["~tEXt@24>24"]);
+
+        let d = png(&[
+            ("IHDR", "head"),
+            ("IDAT", "pixels"),
+            ("tEXt", "new!"),
+            ("IEND", ""),
+        ]);
+        assert_eq!(summary(&diff(&a, &d)), ["~tEXt@24>42"]);
+    }
+
+    #[test]
+    fn test_pairs_by_type() {
+        let a = png(&[("IHDR", "head"), ("tEXt", "a"), ("zTXt", "b"), ("IEND", "")]);
+        let b = png(&[("IHDR", "head"), ("ruSt", "c"), ("zTXt", "d"), ("IEND", "")]);
+        assert_eq!(
+            summary(&diff(&a, &b)),
+            ["-tEXt@24", "~zTXt@37>37", "+ruSt@24"]
+        );
+    }
+
+    #[test]
+    fn test_many_chunks() {
+        let idat = |n: usize| -> Vec<(String, String)> {
+            (0..n)
+                .map(|i| ("IDAT".to_string(), i.to_string()))
+                .collect()
+        };
+        let build = |chunks: &[(String, String)]| {
+            Png::from_chunks(chunks.iter().map(|(t, d)| chunk(t, d)).collect())
+        };
+        // Too many chunks for a full table, but only the middle one differs.
+        let mut a = idat(5000);
+        let b = build(&a);
+        a[2500].1 = "changed".to_string();
+        let a = build(&a);
+        assert_eq!(summary(&diff(&b, &a)).len(), 1);
+
+        // A middle too large to align is reported as removed and added.
+        let c = build(&idat(3000));
+        let d = build(&idat(3000).into_iter().rev().collect::<Vec<_>>());
+        let changes = diff(&c, &d);
+        assert!(changes
+            .iter()
+            .all(|change| matches!(change, Change::Modified { .. })));
+    }
+
+    #[test]
+    fn test_hex_diff() {
+        let old: Vec<u8> = (0..40).collect();
+        let mut new = old.clone();
+        new[20] = 0xff;
+        new.truncate(36);
+        assert_eq!(
+            hex_diff(&old[..32], &old),
+            ["+00000020  20 21 22 23 24 25 26 27"]
+        );
+        assert_eq!(
+            hex_diff(&old, &new),
+            [
+                "-00000010  10 11 12 13 14 15 16 17 18 19 1a 1b 1c 1d 1e 1f",
+                "+00000010  10 11 12 13 ff 15 16 17 18 19 1a 1b 1c 1d 1e 1f",
+                "-00000020  20 21 22 23 24 25 26 27",
+                "+00000020  20 21 22 23",
+            ]
+        );
+        assert!(hex_diff(&old, &old).is_empty());
+    }
+}
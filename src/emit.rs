@@ -0,0 +1,67 @@
+//! Rendering of file contents as source code to compile into a binary.
+
+/// A C `unsigned char` array plus its length, in the style of `xxd -i`.
+pub fn c_array(bytes: &[u8], name: &str) -> String {
+    format!(
+        "unsigned char {name}[] = {{\n{}}};\nunsigned int {name}_len = {};\n",
+        byte_lines(bytes, |b| format!("0x{:02x}", b)),
+        bytes.len()
+    )
+}
+
+/// A Rust `pub const BYTES: &[u8]` item.
+pub fn rust_bytes(bytes: &[u8]) -> String {
+    format!(
+        "pub const BYTES: &[u8] = &[\n{}];\n",
+        byte_lines(bytes, |b| format!("0x{:02x}", b))
+    )
+}
+
+/// Turns a file name into a valid C identifier.
+pub fn identifier(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !ident.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+fn byte_lines(bytes: &[u8], fmt: impl Fn(u8) -> String) -> String {
+    bytes
+        .chunks(12)
+        .map(|line| {
+            let items: Vec<String> = line.iter().map(|&b| fmt(b)).collect();
+            format!("    {},\n", items.join(", "))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c_array() {
+        assert_eq!(
+            c_array(&[0x89, 0x50], "dice_png"),
+            "unsigned char dice_png[] = {\n    0x89, 0x50,\n};\nunsigned int dice_png_len = 2;\n"
+        );
+    }
+
+    #[test]
+    fn test_rust_bytes() {
+        assert_eq!(
+            rust_bytes(&[1; 13]),
+            "pub const BYTES: &[u8] = &[\n    0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01,\n    0x01,\n];\n"
+        );
+    }
+
+    #[test]
+    fn test_identifier() {
+        assert_eq!(identifier("dice.png"), "dice_png");
+        assert_eq!(identifier("1-logo"), "_1_logo");
+    }
+}
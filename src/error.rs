@@ -0,0 +1,131 @@
+//! Broad categories of failure, so callers such as the CLI can tell them
+//! apart without knowing every module's error type.
+
+use crate::chunk::{ChunkError, SignatureError};
+use crate::chunk_type::ChunkTypeError;
+use crate::compression::CompressionError;
+use crate::crypto::CryptoError;
+use crate::ihdr::IhdrError;
+use crate::netpbm::NetpbmError;
+use crate::pixels::PixelError;
+use crate::png::{ParseIssue, PngError};
+use crate::records::RecordsError;
+use crate::split::SplitError;
+use crate::stego::StegoError;
+use crate::store::StoreError;
+use crate::text::TextError;
+use crate::zlib::ZlibError;
+use crate::Error;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PngMeError {
+    /// A file could not be read or written.
+    #[error("{0}")]
+    Io(Error),
+    /// The input is not a valid PNG, or a chunk holds malformed or tampered
+    /// data.
+    #[error("{0}")]
+    Parse(Error),
+    #[error("{0}")]
+    ChunkNotFound(Error),
+    #[error("{0}")]
+    InvalidChunkType(Error),
+    #[error("{0}")]
+    Other(Error),
+}
+
+impl PngMeError {
+    /// The process exit code for this category. 2 is left to usage errors.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            PngMeError::Other(_) => 1,
+            PngMeError::Io(_) => 3,
+            PngMeError::Parse(_) => 4,
+            PngMeError::ChunkNotFound(_) => 5,
+            PngMeError::InvalidChunkType(_) => 6,
+        }
+    }
+}
+
+impl From<Error> for PngMeError {
+    /// Sorts an error from anywhere in the crate into its category.
+    fn from(e: Error) -> PngMeError {
+        let e = match e.downcast::<PngMeError>() {
+            Ok(e) => return *e,
+            Err(e) => e,
+        };
+        if e.is::<std::io::Error>() {
+            return PngMeError::Io(e);
+        }
+        if e.is::<ChunkTypeError>() {
+            return PngMeError::InvalidChunkType(e);
+        }
+        if matches!(e.downcast_ref(), Some(PngError::ChunkNotFound)) {
+            return PngMeError::ChunkNotFound(e);
+        }
+        let parse = e.is::<PngError>()
+            || e.is::<ParseIssue>()
+            || e.is::<ChunkError>()
+            || e.is::<SignatureError>()
+            || e.is::<CompressionError>()
+            || e.is::<CryptoError>()
+            || e.is::<IhdrError>()
+            || e.is::<TextError>()
+            || e.is::<RecordsError>()
+            || e.is::<SplitError>()
+            || e.is::<StegoError>()
+            || e.is::<StoreError>()
+            || e.is::<ZlibError>()
+            || e.is::<PixelError>()
+            || e.is::<NetpbmError>();
+        match parse {
+            true => PngMeError::Parse(e),
+            false => PngMeError::Other(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use crate::ChunkType;
+
+    fn exit_code(e: impl Into<Error>) -> u8 {
+        PngMeError::from(e.into()).exit_code()
+    }
+
+    #[test]
+    fn test_categories() {
+        let missing = std::fs::File::open("/nonexistent/pngme.png").unwrap_err();
+        assert_eq!(exit_code(missing), 3);
+        assert_eq!(exit_code(PngError::WrongHeader), 4);
+        assert_eq!(exit_code(PngError::ChunkNotFound), 5);
+        assert_eq!(exit_code(ChunkType::from_str("rust!").unwrap_err()), 6);
+        assert_eq!(exit_code("something else"), 1);
+    }
+
+    #[test]
+    fn test_module_errors_are_parse_errors() {
+        assert_eq!(exit_code(CryptoError::Decryption), 4);
+        assert_eq!(exit_code(CryptoError::NotEncrypted), 4);
+        assert_eq!(
+            exit_code(StegoError::Unsupported {
+                bit_depth: 1,
+                color_type: 3
+            }),
+            4
+        );
+        assert_eq!(exit_code(StoreError::TooLarge(1)), 4);
+        assert_eq!(exit_code(SplitError::MissingPart(1)), 4);
+    }
+
+    #[test]
+    fn test_already_categorized() {
+        let e = PngMeError::ChunkNotFound("no ruSt chunk".into());
+        let e = PngMeError::from(Error::from(e));
+        assert!(matches!(e, PngMeError::ChunkNotFound(_)));
+        assert_eq!(e.to_string(), "no ruSt chunk");
+    }
+}
@@ -0,0 +1,281 @@
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IhdrError {
+    #[error("IHDR chunk not found")]
+    Missing,
+    #[error("IHDR chunk must be 13 bytes, got {0}")]
+    BadLength(usize),
+    #[error("invalid color type {0}")]
+    BadColorType(u8),
+    #[error("bit depth {bit_depth} is not allowed for color type {color_type}")]
+    BadBitDepth { bit_depth: u8, color_type: u8 },
+    #[error("image dimensions {0}x{1} must be between 1 and 2^31-1")]
+    BadDimensions(u32, u32),
+    #[error("unknown compression method {0}")]
+    BadCompressionMethod(u8),
+    #[error("unknown filter method {0}")]
+    BadFilterMethod(u8),
+    #[error("unknown interlace method {0}")]
+    BadInterlaceMethod(u8),
+    #[error("image of {0}x{1} pixels is too large")]
+    TooLarge(u32, u32),
+}
+
+/// Largest width or height the specification allows.
+const MAX_DIMENSION: u32 = (1 << 31) - 1;
+
+// (x offset, y offset, x step, y step) of each Adam7 pass.
+pub(crate) const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Image header, the first chunk of every PNG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ihdr {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pub compression_method: u8,
+    pub filter_method: u8,
+    pub interlace_method: u8,
+}
+
+impl Ihdr {
+    pub fn from_png(png: &Png) -> std::result::Result<Ihdr, IhdrError> {
+        let chunk = png.chunk_by_type("IHDR").ok_or(IhdrError::Missing)?;
+        Ihdr::try_from(chunk)
+    }
+
+    /// Number of samples per pixel for the color type.
+    pub fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    /// The PNG specification's name for the color type.
+    pub fn color_type_name(&self) -> &'static str {
+        match self.color_type {
+            0 => "grayscale",
+            2 => "truecolor",
+            3 => "indexed-color",
+            4 => "grayscale with alpha",
+            _ => "truecolor with alpha",
+        }
+    }
+
+    /// Whether every pixel carries an alpha sample. Images without one can
+    /// still be transparent through a tRNS chunk.
+    pub fn has_alpha(&self) -> bool {
+        matches!(self.color_type, 4 | 6)
+    }
+
+    pub fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+
+    pub fn is_interlaced(&self) -> bool {
+        self.interlace_method == 1
+    }
+
+    /// Length of the inflated image data, filter bytes included, or an
+    /// error if it does not fit in memory.
+    pub fn image_data_len(&self) -> std::result::Result<usize, IhdrError> {
+        let too_large = || IhdrError::TooLarge(self.width, self.height);
+        let (width, height) = (self.width as usize, self.height as usize);
+        let scanlines = |width: usize, height: usize| {
+            let row = width.checked_mul(self.bits_per_pixel())?.div_ceil(8);
+            row.checked_add(1)?.checked_mul(height)
+        };
+
+        if !self.is_interlaced() {
+            return scanlines(width, height).ok_or_else(too_large);
+        }
+        let mut len: usize = 0;
+        for (x0, y0, dx, dy) in ADAM7 {
+            let pass_width = width.saturating_sub(x0).div_ceil(dx);
+            let pass_height = height.saturating_sub(y0).div_ceil(dy);
+            if pass_width == 0 || pass_height == 0 {
+                continue;
+            }
+            len = scanlines(pass_width, pass_height)
+                .and_then(|pass| len.checked_add(pass))
+                .ok_or_else(too_large)?;
+        }
+        Ok(len)
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.width.to_be_bytes().to_vec();
+        bytes.extend(self.height.to_be_bytes());
+        bytes.extend([
+            self.bit_depth,
+            self.color_type,
+            self.compression_method,
+            self.filter_method,
+            self.interlace_method,
+        ]);
+        bytes
+    }
+
+    pub fn to_chunk(&self) -> Chunk {
+        Chunk::new(ChunkType::IHDR, self.as_bytes())
+    }
+}
+
+impl TryFrom<&Chunk> for Ihdr {
+    type Error = IhdrError;
+
+    fn try_from(chunk: &Chunk) -> std::result::Result<Ihdr, Self::Error> {
+        let data = chunk.data();
+        if data.len() != 13 {
+            return Err(IhdrError::BadLength(data.len()));
+        }
+
+        let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+        let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+        if !(1..=MAX_DIMENSION).contains(&width) || !(1..=MAX_DIMENSION).contains(&height) {
+            return Err(IhdrError::BadDimensions(width, height));
+        }
+
+        let (bit_depth, color_type) = (data[8], data[9]);
+        let allowed_depths: &[u8] = match color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => return Err(IhdrError::BadColorType(color_type)),
+        };
+        if !allowed_depths.contains(&bit_depth) {
+            return Err(IhdrError::BadBitDepth {
+                bit_depth,
+                color_type,
+            });
+        }
+
+        match (data[10], data[11], data[12]) {
+            (0, 0, 0 | 1) => {}
+            (0, 0, interlace) => return Err(IhdrError::BadInterlaceMethod(interlace)),
+            (0, filter, _) => return Err(IhdrError::BadFilterMethod(filter)),
+            (compression, _, _) => return Err(IhdrError::BadCompressionMethod(compression)),
+        }
+
+        Ok(Ihdr {
+            width,
+            height,
+            bit_depth,
+            color_type,
+            compression_method: data[10],
+            filter_method: data[11],
+            interlace_method: data[12],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn ihdr_chunk(color_type: u8) -> Chunk {
+        ihdr_chunk_with(|_| {}, color_type)
+    }
+
+    fn ihdr_chunk_with(edit: impl FnOnce(&mut Vec<u8>), color_type: u8) -> Chunk {
+        let mut data = vec![0, 0, 1, 0, 0, 0, 0, 200, 8, color_type, 0, 0, 1];
+        edit(&mut data);
+        Chunk::new(ChunkType::from_str("IHDR").unwrap(), data)
+    }
+
+    #[test]
+    fn test_from_chunk() {
+        let ihdr = Ihdr::try_from(&ihdr_chunk(6)).unwrap();
+        assert_eq!((ihdr.width, ihdr.height), (256, 200));
+        assert_eq!(ihdr.bit_depth, 8);
+        assert_eq!(ihdr.color_type_name(), "truecolor with alpha");
+        assert!(ihdr.has_alpha());
+        assert!(ihdr.is_interlaced());
+        assert_eq!(ihdr.to_chunk().data(), ihdr_chunk(6).data());
+    }
+
+    #[test]
+    fn test_invalid_chunk() {
+        assert!(matches!(
+            Ihdr::try_from(&ihdr_chunk(5)),
+            Err(IhdrError::BadColorType(5))
+        ));
+        let short = Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 12]);
+        assert!(matches!(
+            Ihdr::try_from(&short),
+            Err(IhdrError::BadLength(12))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_fields() {
+        let invalid = |edit: fn(&mut Vec<u8>)| Ihdr::try_from(&ihdr_chunk_with(edit, 2));
+        assert!(matches!(
+            invalid(|d| d[8] = 0),
+            Err(IhdrError::BadBitDepth { bit_depth: 0, .. })
+        ));
+        assert!(matches!(
+            invalid(|d| d[8] = 4),
+            Err(IhdrError::BadBitDepth { bit_depth: 4, .. })
+        ));
+        assert!(matches!(
+            invalid(|d| d[0..4].fill(0)),
+            Err(IhdrError::BadDimensions(0, 200))
+        ));
+        assert!(matches!(
+            invalid(|d| d[4..8].fill(0xff)),
+            Err(IhdrError::BadDimensions(256, u32::MAX))
+        ));
+        assert!(matches!(
+            invalid(|d| d[10] = 1),
+            Err(IhdrError::BadCompressionMethod(1))
+        ));
+        assert!(matches!(
+            invalid(|d| d[11] = 1),
+            Err(IhdrError::BadFilterMethod(1))
+        ));
+        assert!(matches!(
+            invalid(|d| d[12] = 2),
+            Err(IhdrError::BadInterlaceMethod(2))
+        ));
+        assert!(Ihdr::try_from(&ihdr_chunk_with(|d| d[8] = 1, 3)).is_ok());
+    }
+
+    #[test]
+    fn test_image_data_len() {
+        let ihdr = |edit: fn(&mut Vec<u8>)| Ihdr::try_from(&ihdr_chunk_with(edit, 0)).unwrap();
+        // 256 bytes per row plus the filter byte, 200 rows
+        assert_eq!(ihdr(|d| d[12] = 0).image_data_len().unwrap(), 257 * 200);
+        // 2x2 at one bit per pixel: passes 1, 6 and 7 with one-byte rows
+        let tiny = ihdr(|d| {
+            d[0..8].copy_from_slice(&[0, 0, 0, 2, 0, 0, 0, 2]);
+            d[8] = 1;
+        });
+        assert_eq!(tiny.image_data_len().unwrap(), 2 + 2 + 2);
+
+        let huge = ihdr(|d| {
+            d[0..8].copy_from_slice(&[0x7f, 0xff, 0xff, 0xff, 0x7f, 0xff, 0xff, 0xff]);
+            d[8..10].copy_from_slice(&[16, 6]);
+        });
+        assert!(matches!(
+            huge.image_data_len(),
+            Err(IhdrError::TooLarge(_, _))
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+//! Diagrams of a file's chunk sequence, in SVG or Graphviz DOT.
+
+use crate::png::Png;
+
+const CRITICAL_COLOR: &str = "#f8cecc";
+const ANCILLARY_COLOR: &str = "#dae8fc";
+const SIGNATURE_COLOR: &str = "#e1e1e1";
+
+struct Block {
+    label: String,
+    offset: u64,
+    size: usize,
+    color: &'static str,
+}
+
+/// The signature followed by one block per chunk, with file offsets.
+fn blocks(png: &Png) -> Vec<Block> {
+    let mut blocks = vec![Block {
+        label: "signature".to_string(),
+        offset: 0,
+        size: Png::STANDARD_HEADER.len(),
+        color: SIGNATURE_COLOR,
+    }];
+    for (offset, chunk) in png.chunks_with_offsets() {
+        // length + type + data + crc
+        let size = 4 + 4 + chunk.length() as usize + 4;
+        blocks.push(Block {
+            label: chunk.chunk_type().to_string(),
+            offset,
+            size,
+            color: if chunk.chunk_type().is_critical() {
+                CRITICAL_COLOR
+            } else {
+                ANCILLARY_COLOR
+            },
+        });
+    }
+    blocks
+}
+
+pub fn svg(png: &Png) -> String {
+    const WIDTH: usize = 480;
+    const ROW: usize = 36;
+
+    let blocks = blocks(png);
+    let height = blocks.len() * ROW + 8;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"monospace\" font-size=\"13\">\n",
+        w = WIDTH + 8,
+        h = height
+    );
+    for (idx, block) in blocks.iter().enumerate() {
+        let y = 4 + idx * ROW;
+        svg.push_str(&format!(
+            "  <rect x=\"4\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"#333\"/>\n",
+            y,
+            WIDTH,
+            ROW - 4,
+            block.color
+        ));
+        svg.push_str(&format!(
+            "  <text x=\"14\" y=\"{}\">{}</text>\n",
+            y + 21,
+            block.label
+        ));
+        svg.push_str(&format!(
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"end\">offset {}, {} bytes</text>\n",
+            WIDTH - 6,
+            y + 21,
+            block.offset,
+            block.size
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+pub fn dot(png: &Png) -> String {
+    let blocks = blocks(png);
+    let mut dot = String::from(
+        "digraph png {\n  rankdir=TB;\n  node [shape=record, style=filled, fontname=\"monospace\"];\n",
+    );
+    for (idx, block) in blocks.iter().enumerate() {
+        dot.push_str(&format!(
+            "  n{} [label=\"{{{}|offset {}|{} bytes}}\", fillcolor=\"{}\"];\n",
+            idx, block.label, block.offset, block.size, block.color
+        ));
+    }
+    for idx in 1..blocks.len() {
+        dot.push_str(&format!("  n{} -> n{};\n", idx - 1, idx));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn testing_png() -> Png {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hi".to_vec()),
+        ])
+    }
+
+    #[test]
+    fn test_dot() {
+        let dot = dot(&testing_png());
+        assert!(dot.contains("n1 [label=\"{IHDR|offset 8|25 bytes}\", fillcolor=\"#f8cecc\"];"));
+        assert!(dot.contains("n2 [label=\"{ruSt|offset 33|14 bytes}\", fillcolor=\"#dae8fc\"];"));
+        assert!(dot.contains("n1 -> n2;"));
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = svg(&testing_png());
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 3);
+        assert!(svg.contains("offset 33, 14 bytes"));
+    }
+}
@@ -0,0 +1,70 @@
+//! Reading, editing and writing PNG files chunk by chunk.
+//!
+//! ```
+//! use std::str::FromStr;
+//! use pngme::{Chunk, ChunkType, Png};
+//!
+//! let mut png = Png::from_chunks(vec![]);
+//! let chunk_type = ChunkType::from_str("ruSt").unwrap();
+//! png.append_chunk(Chunk::new(chunk_type, b"A secret message!".to_vec()));
+//!
+//! let png = Png::try_from(&png.as_bytes()[..]).unwrap();
+//! assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"A secret message!");
+//! ```
+//!
+//! Messages are stored in the format the `pngme` tool uses, so
+//! [`decode_message`] reads what `pngme encode` wrote, and the other way
+//! round. [`encode_payload`] and [`decode_payload`] apply the optional
+//! compression, encryption and signature layers:
+//!
+//! ```
+//! use pngme::compression::Method;
+//! use pngme::{decode_payload, encode_payload, DecodeOptions, EncodeOptions};
+//!
+//! let options = EncodeOptions {
+//!     compress: Some(Method::Deflate),
+//!     ..EncodeOptions::default()
+//! };
+//! let payload = encode_payload(b"A secret message!", &options).unwrap();
+//! let message = decode_payload(&payload, &DecodeOptions::default()).unwrap();
+//! assert_eq!(message, b"A secret message!");
+//! ```
+
+pub mod chunk;
+pub mod chunk_index;
+pub mod chunk_type;
+pub mod compression;
+pub mod crypto;
+pub mod diff;
+pub mod emit;
+pub mod error;
+pub mod ihdr;
+pub mod layout;
+pub mod magic;
+pub mod message;
+pub mod netpbm;
+pub mod pixels;
+pub mod png;
+pub mod records;
+pub mod report;
+pub mod scan;
+pub mod similarity;
+pub mod split;
+pub mod stego;
+pub mod store;
+pub mod stream;
+pub mod template;
+pub mod text;
+pub mod zlib;
+
+pub use chunk::{Chunk, ChunkError};
+pub use chunk_type::{ChunkType, ChunkTypeError};
+pub use error::PngMeError;
+pub use message::{
+    decode_message, decode_payload, encode_message, encode_payload, remove_message, DecodeOptions,
+    EncodeOptions,
+};
+pub use png::{Png, PngError};
+
+pub type Error = Box<dyn std::error::Error>;
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,38 @@
+//! Recognition of common file formats by their leading bytes.
+
+/// Format names and the signatures their files start with.
+pub const MAGICS: &[(&str, &[u8])] = &[
+    ("png", b"\x89PNG\r\n\x1a\n"),
+    ("jpeg", b"\xff\xd8\xff"),
+    ("gif", b"GIF8"),
+    ("zip", b"PK\x03\x04"),
+    ("gzip", b"\x1f\x8b"),
+    ("7z", b"7z\xbc\xaf\x27\x1c"),
+    ("rar", b"Rar!\x1a\x07"),
+    ("pdf", b"%PDF-"),
+    ("elf", b"\x7fELF"),
+    ("pe", b"MZ"),
+    ("zstd", b"\x28\xb5\x2f\xfd"),
+    ("xz", b"\xfd7zXZ\x00"),
+    ("bzip2", b"BZh"),
+];
+
+/// Name of the file format `data` starts with, if it is a known one.
+pub fn detect(data: &[u8]) -> Option<&'static str> {
+    MAGICS
+        .iter()
+        .find(|(_, magic)| data.starts_with(magic))
+        .map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect(b"PK\x03\x04rest of archive"), Some("zip"));
+        assert_eq!(detect(b"%PDF-1.7"), Some("pdf"));
+        assert_eq!(detect(b"just some text"), None);
+    }
+}
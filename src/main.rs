@@ -1,81 +1,48 @@
-use clap::{Parser, ValueEnum};
-use std::{io::Write, path::PathBuf, str::FromStr};
+use std::process::ExitCode;
 
-use crate::{chunk::Chunk, chunk_type::ChunkType, png::Png};
+use clap::Parser;
 
-mod chunk;
-mod chunk_type;
-mod commands;
-mod png;
-
-pub type Error = Box<dyn std::error::Error>;
-pub type Result<T> = std::result::Result<T, Error>;
-
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, ValueEnum)]
-enum Command {
-    Encode,
-    Decode,
-    Remove,
-    Print,
-}
-
-#[derive(clap::Parser, Debug)]
-#[command(name = "")]
-struct Args {
-    /// command to run
-    #[arg(value_enum)]
-    command: Command,
-
-    /// input/output file
-    #[arg(value_name = "FILE")]
-    file_path: PathBuf,
-
-    /// chunk type, ignore for print
-    #[arg(value_name = "CHUNK")]
-    chunk_type: Option<String>,
-
-    /// message to encode, ignored for other commands
-    #[arg(value_name = "MESSAGE")]
-    message: Option<String>,
-}
+use args::{Cli, Command};
+use pngme::{PngMeError, Result};
 
-fn main() -> Result<()> {
-    let args = Args::parse();
-
-    let file_content = std::fs::read(&args.file_path).unwrap();
-    let mut png = Png::try_from(&file_content[..]).unwrap();
-
-    let mut overwrite_file = false;
-    match args.command {
-        Command::Encode => {
-            let chunk_type = ChunkType::from_str(&args.chunk_type.unwrap()).unwrap();
-            let new_chunk = Chunk::new(chunk_type, args.message.unwrap().as_bytes().to_vec());
-            png.append_chunk(new_chunk);
-            overwrite_file = true;
-        }
-        Command::Decode => {
-            let chunk = png.chunk_by_type(&args.chunk_type.unwrap()).unwrap();
-            println!("{}", chunk);
-        }
-        Command::Remove => {
-            let removed = png.remove_chunk(&args.chunk_type.unwrap()).unwrap();
-            println!("Removed: {}", removed);
-            overwrite_file = true;
-        }
-        Command::Print => {
-            println!("{}", png);
+mod args;
+mod commands;
+mod storage;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let e = PngMeError::from(e);
+            eprintln!("error: {}", e);
+            ExitCode::from(e.exit_code())
         }
     }
+}
 
-    if overwrite_file {
-        let mut f = std::fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(&args.file_path)?;
-
-        f.write_all(&png.as_bytes()[..]).unwrap();
-        f.flush().unwrap();
+fn run(cli: Cli) -> Result<()> {
+    match cli.command {
+        Command::Encode(args) => commands::encode(args, &cli.global),
+        Command::Decode(args) => commands::decode(args, &cli.global),
+        Command::Remove(args) => commands::remove(args, &cli.global),
+        Command::Print(args) => commands::print(args, &cli.global),
+        Command::Keys(args) => commands::keys(args, &cli.global),
+        Command::Info(args) => commands::info(args, &cli.global),
+        Command::Dump(args) => commands::dump(args),
+        Command::Scan(args) => commands::scan(args, &cli.global),
+        Command::Diff(args) => commands::diff(args, &cli.global),
+        Command::Layout(args) => commands::layout(args),
+        Command::Checksum(args) => commands::checksum(args),
+        Command::Compress(args) => commands::compress(args, &cli.global),
+        Command::Validate(args) => commands::validate(args, &cli.global),
+        Command::Index(args) => commands::index(args),
+        Command::LicenseScan(args) => commands::license_scan(args),
+        Command::DedupeImages(args) => commands::dedupe_images(args),
+        Command::Mirror(args) => commands::mirror(args, &cli.global),
+        Command::Wizard => commands::wizard(&cli.global),
+        Command::Strip(args) => commands::strip(args, &cli.global),
+        Command::StegoEncode(args) => commands::stego_encode(args, &cli.global),
+        Command::StegoDecode(args) => commands::stego_decode(args),
     }
-
-    Ok(())
 }
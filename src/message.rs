@@ -0,0 +1,237 @@
+//! The format messages are stored in, shared by the CLI and the bindings: the
+//! layers a message is wrapped in and the chunks that hold it. Also encode,
+//! decode and remove on PNG bytes held in memory, for callers that have no
+//! filesystem, such as the wasm and Node.js bindings.
+
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use crate::chunk::{self, Chunk};
+use crate::chunk_type::ChunkType;
+use crate::compression::{self, Method};
+use crate::crypto;
+use crate::png::Png;
+use crate::split;
+use crate::Result;
+
+/// How [`encode_payload`] wraps a message. The default stores it as is.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EncodeOptions<'a> {
+    /// Compress the message with this method.
+    pub compress: Option<Method>,
+    /// Encrypt the message with this passphrase.
+    pub passphrase: Option<&'a str>,
+    /// Sign the message with this key.
+    pub signing_key: Option<&'a [u8]>,
+    /// The payload goes inside another format, such as a record or a text
+    /// chunk, instead of making up the chunk data, and is not escaped.
+    pub embedded: bool,
+}
+
+/// How [`decode_payload`] unwraps a message.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DecodeOptions<'a> {
+    /// Decrypt the message with this passphrase. Without one, an encrypted
+    /// message is returned encrypted.
+    pub passphrase: Option<&'a str>,
+    /// Check the signature under this key. Without one, a signature is
+    /// stripped unchecked.
+    pub verify_key: Option<&'a [u8]>,
+}
+
+/// Wraps `message` in the layers `options` ask for: compression, then
+/// encryption, then a signature. A layer that is left out is escaped instead
+/// when the message would otherwise look like it, so that
+/// [`decode_payload`] returns the message as it was.
+pub fn encode_payload(message: &[u8], options: &EncodeOptions) -> Result<Vec<u8>> {
+    let mut payload = match options.compress {
+        Some(method) => compression::compress(message, method),
+        None if options.embedded => message.to_vec(),
+        None => compression::escape(message).into_owned(),
+    };
+    if let Some(passphrase) = options.passphrase {
+        payload = crypto::encrypt(&payload, passphrase)?;
+    }
+    Ok(match options.signing_key {
+        Some(key) => chunk::sign(&payload, key),
+        None if options.embedded => payload,
+        None => chunk::escape_unsigned(&payload).into_owned(),
+    })
+}
+
+/// Unwraps a payload made by [`encode_payload`]: checks or strips the
+/// signature, decrypts the message if given a passphrase and decompresses it.
+pub fn decode_payload(payload: &[u8], options: &DecodeOptions) -> Result<Vec<u8>> {
+    let payload = match options.verify_key {
+        Some(key) => chunk::verify(payload, key)?,
+        None => match chunk::split_signed(payload) {
+            Ok((_, _, payload)) => payload,
+            // Data that only starts like a signed message is a plain message.
+            Err(_) => chunk::unsigned_payload(payload),
+        },
+    };
+    let payload = match options.passphrase {
+        Some(passphrase) => Cow::Owned(crypto::decrypt(payload, passphrase)?),
+        None => Cow::Borrowed(payload),
+    };
+    Ok(match compression::decompress(&payload) {
+        Some(message) => message?,
+        None => payload.into_owned(),
+    })
+}
+
+/// The chunks of `chunk_type` holding `payload`: a single chunk, or parts of
+/// at most `split` bytes each.
+pub fn payload_chunks(
+    chunk_type: ChunkType,
+    payload: &[u8],
+    split: Option<usize>,
+) -> Result<Vec<Chunk>> {
+    Ok(match split {
+        Some(max_len) => split::split(&chunk_type, payload, max_len),
+        None => vec![Chunk::try_new(
+            chunk_type,
+            split::escape(payload).into_owned(),
+        )?],
+    })
+}
+
+/// The payload of a single chunk made by [`payload_chunks`]: its data, or
+/// the payload of a message that looked like a split part and was therefore
+/// stored as a part of its own.
+pub fn chunk_payload(chunk: &Chunk) -> Cow<'_, [u8]> {
+    match split::join([chunk]) {
+        Some(Ok(payload)) => Cow::Owned(payload),
+        _ => Cow::Borrowed(chunk.data()),
+    }
+}
+
+/// The payload held by the chunks of `chunk_type` made by
+/// [`payload_chunks`]: the parts put back together, or the payload of the
+/// first chunk. `None` if there is no such chunk.
+pub fn read_payload(png: &Png, chunk_type: &str) -> Result<Option<Vec<u8>>> {
+    if let Some(payload) = split::join(png.chunks_by_type(chunk_type)) {
+        return Ok(Some(payload?));
+    }
+    Ok(png
+        .chunk_by_type(chunk_type)
+        .map(|chunk| chunk_payload(chunk).into_owned()))
+}
+
+/// Returns a copy of `png_bytes` with `message` in a chunk of `chunk_type`
+/// inserted before IEND, stored as `encode` stores it without options.
+pub fn encode_message(png_bytes: &[u8], chunk_type: &str, message: &[u8]) -> Result<Vec<u8>> {
+    let mut png = Png::try_from(png_bytes)?;
+    let chunk_type = ChunkType::from_str(chunk_type)?;
+    let payload = encode_payload(message, &EncodeOptions::default())?;
+    png.insert_before_iend(payload_chunks(chunk_type, &payload, None)?);
+    Ok(png.as_bytes())
+}
+
+/// The message stored in the chunks of `chunk_type`, unwrapped as `decode`
+/// unwraps it without options, or `None` if there is no such chunk.
+pub fn decode_message(png_bytes: &[u8], chunk_type: &str) -> Result<Option<Vec<u8>>> {
+    let png = Png::try_from(png_bytes)?;
+    read_payload(&png, chunk_type)?
+        .map(|payload| decode_payload(&payload, &DecodeOptions::default()))
+        .transpose()
+}
+
+/// Returns a copy of `png_bytes` without the first chunk of `chunk_type`.
+pub fn remove_message(png_bytes: &[u8], chunk_type: &str) -> Result<Vec<u8>> {
+    let mut png = Png::try_from(png_bytes)?;
+    png.remove_chunk(chunk_type)?;
+    Ok(png.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_png() -> Vec<u8> {
+        let chunk = |t: ChunkType| Chunk::new(t, vec![]);
+        Png::from_chunks(vec![chunk(ChunkType::IHDR), chunk(ChunkType::IEND)]).as_bytes()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let png = testing_png();
+        assert_eq!(decode_message(&png, "ruSt").unwrap(), None);
+
+        let encoded = encode_message(&png, "ruSt", b"A secret message!").unwrap();
+        assert_eq!(
+            decode_message(&encoded, "ruSt").unwrap().as_deref(),
+            Some(&b"A secret message!"[..])
+        );
+
+        let removed = remove_message(&encoded, "ruSt").unwrap();
+        assert_eq!(removed, png);
+        assert!(remove_message(&removed, "ruSt").is_err());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(encode_message(b"not a png", "ruSt", b"").is_err());
+        assert!(encode_message(&testing_png(), "ru5t", b"").is_err());
+        assert!(decode_message(&[], "ruSt").is_err());
+    }
+
+    #[test]
+    fn test_payload_layers() {
+        let message = "A secret message! ".repeat(20);
+        let options = EncodeOptions {
+            compress: Some(Method::Deflate),
+            passphrase: Some("passphrase"),
+            signing_key: Some(b"key"),
+            embedded: false,
+        };
+        let payload = encode_payload(message.as_bytes(), &options).unwrap();
+        assert!(chunk::is_signed(&payload));
+
+        let options = DecodeOptions {
+            passphrase: Some("passphrase"),
+            verify_key: Some(b"key"),
+        };
+        assert_eq!(
+            decode_payload(&payload, &options).unwrap(),
+            message.as_bytes()
+        );
+        let wrong_key = DecodeOptions {
+            verify_key: Some(b"other key"),
+            ..options
+        };
+        assert!(decode_payload(&payload, &wrong_key).is_err());
+    }
+
+    #[test]
+    fn test_messages_looking_like_layers() {
+        let mut signed_header = b"PNGS\x01\x00".to_vec();
+        signed_header.extend([7; 32]);
+        signed_header.extend(b"TAIL");
+        for message in [
+            &b"PNGZ!hello"[..],
+            b"SPLThello world",
+            b"PNGME not encrypted",
+            &signed_header,
+        ] {
+            let encoded = encode_message(&testing_png(), "ruSt", message).unwrap();
+            assert_eq!(
+                decode_message(&encoded, "ruSt").unwrap().as_deref(),
+                Some(message)
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_payload() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let mut png = Png::try_from(&testing_png()[..]).unwrap();
+        let payload = encode_payload(&[1; 100], &EncodeOptions::default()).unwrap();
+        png.insert_before_iend(payload_chunks(chunk_type, &payload, Some(40)).unwrap());
+        assert_eq!(png.chunks_by_type("ruSt").count(), 4);
+        assert_eq!(
+            decode_message(&png.as_bytes(), "ruSt").unwrap(),
+            Some(vec![1; 100])
+        );
+    }
+}
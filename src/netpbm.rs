@@ -0,0 +1,234 @@
+//! Decoding of binary Netpbm images (PGM `P5`, PPM `P6` and PAM `P7`) as
+//! produced by ImageMagick and ffmpeg pipelines.
+
+use crate::ihdr::Ihdr;
+use crate::pixels::Pixels;
+
+#[derive(Debug, thiserror::Error)]
+pub enum NetpbmError {
+    #[error("not a binary PGM, PPM or PAM image")]
+    UnsupportedFormat,
+    #[error("invalid header: {0}")]
+    BadHeader(String),
+    #[error("image data is truncated")]
+    Truncated,
+}
+
+struct Header {
+    width: u32,
+    height: u32,
+    depth: usize,
+    maxval: u32,
+    data_offset: usize,
+}
+
+/// Decodes a Netpbm image into pixels ready to be encoded as PNG. Samples with
+/// a maximum value other than 255 or 65535 are rescaled.
+pub fn decode(bytes: &[u8]) -> std::result::Result<Pixels, NetpbmError> {
+    let header = match bytes.get(..2) {
+        Some(b"P5") => parse_pnm_header(bytes, 1)?,
+        Some(b"P6") => parse_pnm_header(bytes, 3)?,
+        Some(b"P7") => parse_pam_header(bytes)?,
+        _ => return Err(NetpbmError::UnsupportedFormat),
+    };
+    if header.maxval == 0 || header.maxval > 65535 {
+        return Err(NetpbmError::BadHeader(format!("maxval {}", header.maxval)));
+    }
+    let color_type = match header.depth {
+        1 => 0,
+        2 => 4,
+        3 => 2,
+        4 => 6,
+        depth => return Err(NetpbmError::BadHeader(format!("depth {}", depth))),
+    };
+
+    let max_dimension = (1 << 31) - 1;
+    if !(1..=max_dimension).contains(&header.width) || !(1..=max_dimension).contains(&header.height)
+    {
+        return Err(NetpbmError::BadHeader(format!(
+            "dimensions {}x{}",
+            header.width, header.height
+        )));
+    }
+
+    let wide = header.maxval > 255;
+    let sample_len = if wide { 2 } else { 1 };
+    let too_large = || {
+        NetpbmError::BadHeader(format!(
+            "image of {}x{} pixels is too large",
+            header.width, header.height
+        ))
+    };
+    let len = (header.width as usize)
+        .checked_mul(header.height as usize)
+        .and_then(|pixels| pixels.checked_mul(header.depth * sample_len))
+        .ok_or_else(too_large)?;
+    let end = header.data_offset.checked_add(len).ok_or_else(too_large)?;
+    let raw = bytes
+        .get(header.data_offset..end)
+        .ok_or(NetpbmError::Truncated)?;
+
+    let data = match header.maxval {
+        255 | 65535 => raw.to_vec(),
+        maxval if wide => raw
+            .chunks(2)
+            .flat_map(|s| {
+                let v = u16::from_be_bytes([s[0], s[1]]) as u32;
+                ((v.min(maxval) * 65535 / maxval) as u16).to_be_bytes()
+            })
+            .collect(),
+        maxval => raw
+            .iter()
+            .map(|&v| ((v as u32).min(maxval) * 255 / maxval) as u8)
+            .collect(),
+    };
+
+    Ok(Pixels {
+        ihdr: Ihdr {
+            width: header.width,
+            height: header.height,
+            bit_depth: if wide { 16 } else { 8 },
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        },
+        data,
+    })
+}
+
+/// P5/P6: magic, width, height and maxval separated by whitespace (with `#`
+/// comments), then a single whitespace byte before the samples.
+fn parse_pnm_header(bytes: &[u8], depth: usize) -> std::result::Result<Header, NetpbmError> {
+    let mut pos = 2;
+    let mut fields = [0u32; 3];
+    for field in fields.iter_mut() {
+        loop {
+            match bytes.get(pos) {
+                Some(b) if b.is_ascii_whitespace() => pos += 1,
+                Some(b'#') => {
+                    while bytes.get(pos).is_some_and(|&b| b != b'\n') {
+                        pos += 1;
+                    }
+                }
+                Some(_) => break,
+                None => return Err(NetpbmError::Truncated),
+            }
+        }
+        let start = pos;
+        while bytes.get(pos).is_some_and(u8::is_ascii_digit) {
+            pos += 1;
+        }
+        *field = parse_number(&bytes[start..pos])?;
+    }
+    if !bytes.get(pos).is_some_and(u8::is_ascii_whitespace) {
+        return Err(NetpbmError::BadHeader(
+            "missing separator before data".into(),
+        ));
+    }
+
+    Ok(Header {
+        width: fields[0],
+        height: fields[1],
+        depth,
+        maxval: fields[2],
+        data_offset: pos + 1,
+    })
+}
+
+/// P7: `KEY value` lines up to `ENDHDR`.
+fn parse_pam_header(bytes: &[u8]) -> std::result::Result<Header, NetpbmError> {
+    let (mut width, mut height, mut depth, mut maxval) = (None, None, None, None);
+    let mut pos = 0;
+    loop {
+        let end = bytes[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or(NetpbmError::Truncated)?;
+        let line = String::from_utf8_lossy(&bytes[pos..pos + end]).to_string();
+        pos += end + 1;
+
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("ENDHDR") => break,
+            Some("WIDTH") => width = Some(parse_number(words.next().unwrap_or("").as_bytes())?),
+            Some("HEIGHT") => height = Some(parse_number(words.next().unwrap_or("").as_bytes())?),
+            Some("DEPTH") => depth = Some(parse_number(words.next().unwrap_or("").as_bytes())?),
+            Some("MAXVAL") => maxval = Some(parse_number(words.next().unwrap_or("").as_bytes())?),
+            // P7, TUPLTYPE, comments and blank lines
+            _ => {}
+        }
+    }
+
+    let missing = |name: &str| NetpbmError::BadHeader(format!("missing {}", name));
+    Ok(Header {
+        width: width.ok_or_else(|| missing("WIDTH"))?,
+        height: height.ok_or_else(|| missing("HEIGHT"))?,
+        depth: depth.ok_or_else(|| missing("DEPTH"))? as usize,
+        maxval: maxval.ok_or_else(|| missing("MAXVAL"))?,
+        data_offset: pos,
+    })
+}
+
+fn parse_number(digits: &[u8]) -> std::result::Result<u32, NetpbmError> {
+    std::str::from_utf8(digits)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| {
+            NetpbmError::BadHeader(format!(
+                "expected a number, got {:?}",
+                String::from_utf8_lossy(digits)
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_ppm() {
+        let mut bytes = b"P6\n# comment\n2 1\n255\n".to_vec();
+        bytes.extend([1, 2, 3, 4, 5, 6]);
+        let pixels = decode(&bytes).unwrap();
+        assert_eq!((pixels.ihdr.width, pixels.ihdr.height), (2, 1));
+        assert_eq!(pixels.ihdr.color_type, 2);
+        assert_eq!(pixels.data, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_decode_pgm_rescales_samples() {
+        let mut bytes = b"P5 2 1 15 ".to_vec();
+        bytes.extend([0, 15]);
+        let pixels = decode(&bytes).unwrap();
+        assert_eq!(pixels.ihdr.color_type, 0);
+        assert_eq!(pixels.data, vec![0, 255]);
+    }
+
+    #[test]
+    fn test_decode_pam() {
+        let mut bytes =
+            b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n".to_vec();
+        bytes.extend([9, 8, 7, 6]);
+        let pixels = decode(&bytes).unwrap();
+        assert_eq!(pixels.ihdr.color_type, 6);
+        assert_eq!(pixels.data, vec![9, 8, 7, 6]);
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        assert!(decode(b"P6 2 2 255 \x01\x02").is_err());
+        assert!(decode(b"P3 1 1 255 0 0 0").is_err());
+    }
+
+    #[test]
+    fn test_decode_bad_dimensions() {
+        for header in [
+            &b"P6\n4294967295 4294967295\n255\n"[..],
+            b"P6\n2147483647 2147483647\n65535\n",
+            b"P5 0 1 255 ",
+        ] {
+            assert!(matches!(decode(header), Err(NetpbmError::BadHeader(_))));
+        }
+    }
+}
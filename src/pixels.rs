@@ -0,0 +1,407 @@
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::ihdr::{Ihdr, IhdrError, ADAM7};
+use crate::png::Png;
+use crate::zlib::{self, ZlibError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PixelError {
+    #[error(transparent)]
+    Ihdr(#[from] IhdrError),
+    #[error("no IDAT chunk found")]
+    MissingData,
+    #[error("failed to inflate image data: {0}")]
+    Inflate(#[from] ZlibError),
+    #[error("image data is truncated")]
+    Truncated,
+    #[error("unknown filter type {0}")]
+    BadFilter(u8),
+    #[error("paletted image without a usable PLTE chunk")]
+    MissingPalette,
+}
+
+/// Decoded image data: unfiltered, de-interlaced scanlines without filter bytes.
+pub struct Pixels {
+    pub ihdr: Ihdr,
+    pub data: Vec<u8>,
+}
+
+impl Pixels {
+    pub fn decode(png: &Png) -> std::result::Result<Pixels, PixelError> {
+        let ihdr = Ihdr::from_png(png)?;
+        let expected_len = ihdr.image_data_len()?;
+        // Inflating stops at the length the header implies, so a small IDAT
+        // cannot expand into more memory than the image needs, and the
+        // header cannot claim more than the image data actually holds.
+        let filtered = inflate_image_data(png, expected_len)?;
+        if filtered.len() < expected_len {
+            return Err(PixelError::Truncated);
+        }
+
+        let width = ihdr.width as usize;
+        let height = ihdr.height as usize;
+        let bpp = ihdr.bits_per_pixel();
+
+        let data = if ihdr.is_interlaced() {
+            let mut out = vec![0; row_len(width, bpp) * height];
+            let mut offset = 0;
+            for (x0, y0, dx, dy) in ADAM7 {
+                let pass_width = width.saturating_sub(x0).div_ceil(dx);
+                let pass_height = height.saturating_sub(y0).div_ceil(dy);
+                if pass_width == 0 || pass_height == 0 {
+                    continue;
+                }
+
+                let pass_len = (row_len(pass_width, bpp) + 1) * pass_height;
+                let pass_data = filtered
+                    .get(offset..offset + pass_len)
+                    .ok_or(PixelError::Truncated)?;
+                offset += pass_len;
+
+                let pass = unfilter(pass_data, pass_width, pass_height, bpp)?;
+                let pass_row_len = row_len(pass_width, bpp);
+                for py in 0..pass_height {
+                    let row = &pass[py * pass_row_len..(py + 1) * pass_row_len];
+                    let y = y0 + py * dy;
+                    for px in 0..pass_width {
+                        let x = x0 + px * dx;
+                        copy_pixel(row, px, &mut out, y * row_len(width, bpp), x, bpp);
+                    }
+                }
+            }
+            out
+        } else {
+            unfilter(&filtered, width, height, bpp)?
+        };
+
+        Ok(Pixels { ihdr, data })
+    }
+
+    pub fn row_len(&self) -> usize {
+        row_len(self.ihdr.width as usize, self.ihdr.bits_per_pixel())
+    }
+
+    /// 8-bit luminance of every pixel, row by row. Paletted images need the
+    /// PLTE chunk data to be passed as `palette`.
+    pub fn luma(&self, palette: Option<&[u8]>) -> std::result::Result<Vec<u8>, PixelError> {
+        let depth = self.ihdr.bit_depth as usize;
+        let channels = self.ihdr.channels();
+        let row_len = self.row_len();
+        let max = ((1u32 << depth.min(16)) - 1) as f32;
+
+        let sample = |row: &[u8], idx: usize| -> u32 {
+            match depth {
+                16 => u16::from_be_bytes([row[idx * 2], row[idx * 2 + 1]]) as u32,
+                8 => row[idx] as u32,
+                _ => {
+                    let shift = 8 - depth - (idx * depth) % 8;
+                    ((row[idx * depth / 8] >> shift) as u32) & ((1 << depth) - 1)
+                }
+            }
+        };
+        let scale = |v: u32| (v as f32 * 255.0 / max).round() as u32;
+        let rgb_luma = |r: u32, g: u32, b: u32| ((r * 299 + g * 587 + b * 114) / 1000) as u8;
+
+        let mut luma = Vec::with_capacity(self.ihdr.width as usize * self.ihdr.height as usize);
+        for row in self.data.chunks(row_len.max(1)) {
+            for x in 0..self.ihdr.width as usize {
+                let first = x * channels;
+                luma.push(match self.ihdr.color_type {
+                    2 | 6 => rgb_luma(
+                        scale(sample(row, first)),
+                        scale(sample(row, first + 1)),
+                        scale(sample(row, first + 2)),
+                    ),
+                    3 => {
+                        let palette = palette.ok_or(PixelError::MissingPalette)?;
+                        let entry = sample(row, x) as usize * 3;
+                        let rgb = palette
+                            .get(entry..entry + 3)
+                            .ok_or(PixelError::MissingPalette)?;
+                        rgb_luma(rgb[0] as u32, rgb[1] as u32, rgb[2] as u32)
+                    }
+                    _ => scale(sample(row, first)) as u8,
+                });
+            }
+        }
+        Ok(luma)
+    }
+
+    /// Filters and deflates the image into the data of a single IDAT chunk
+    /// of a non-interlaced image.
+    pub fn compress(&self) -> Vec<u8> {
+        zlib::deflate(&filter(
+            &self.data,
+            self.row_len(),
+            self.ihdr.bits_per_pixel(),
+        ))
+    }
+
+    /// Encodes the image as a minimal non-interlaced PNG: IHDR, a single
+    /// IDAT and IEND. Scanlines are stored unfiltered.
+    pub fn to_png(&self) -> Png {
+        let ihdr = Ihdr {
+            interlace_method: 0,
+            ..self.ihdr
+        };
+
+        let row_len = self.row_len();
+        let mut filtered = Vec::with_capacity((row_len + 1) * ihdr.height as usize);
+        for row in self.data.chunks(row_len.max(1)) {
+            filtered.push(0);
+            filtered.extend(row);
+        }
+
+        Png::from_chunks(vec![
+            ihdr.to_chunk(),
+            Chunk::new(ChunkType::IDAT, zlib::deflate(&filtered)),
+            Chunk::new(ChunkType::IEND, vec![]),
+        ])
+    }
+}
+
+/// Concatenates and inflates the zlib stream carried by the IDAT chunks,
+/// failing if it inflates to more than `limit` bytes.
+pub fn inflate_image_data(png: &Png, limit: usize) -> std::result::Result<Vec<u8>, PixelError> {
+    let compressed: Vec<u8> = png
+        .chunks()
+        .iter()
+        .filter(|c| *c.chunk_type() == ChunkType::IDAT)
+        .flat_map(|c| c.data().iter().copied())
+        .collect();
+    if compressed.is_empty() {
+        return Err(PixelError::MissingData);
+    }
+
+    Ok(zlib::inflate_limited(&compressed, limit)?)
+}
+
+fn row_len(width: usize, bits_per_pixel: usize) -> usize {
+    (width * bits_per_pixel).div_ceil(8)
+}
+
+fn unfilter(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    bits_per_pixel: usize,
+) -> std::result::Result<Vec<u8>, PixelError> {
+    let stride = row_len(width, bits_per_pixel);
+    let bpp = bits_per_pixel.div_ceil(8).max(1);
+    if (stride + 1)
+        .checked_mul(height)
+        .is_none_or(|len| data.len() < len)
+    {
+        return Err(PixelError::Truncated);
+    }
+
+    let mut out = vec![0u8; stride * height];
+    for y in 0..height {
+        let filter = data[y * (stride + 1)];
+        if filter > 4 {
+            return Err(PixelError::BadFilter(filter));
+        }
+        let src = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (prev_rows, rest) = out.split_at_mut(y * stride);
+        let prev = if y == 0 {
+            None
+        } else {
+            Some(&prev_rows[(y - 1) * stride..])
+        };
+        let cur = &mut rest[..stride];
+
+        for x in 0..stride {
+            let a = if x >= bpp { cur[x - bpp] } else { 0 };
+            let b = prev.map_or(0, |p| p[x]);
+            let c = match prev {
+                Some(p) if x >= bpp => p[x - bpp],
+                _ => 0,
+            };
+            cur[x] = src[x].wrapping_add(predict(filter, a, b, c));
+        }
+    }
+    Ok(out)
+}
+
+/// Filters every scanline of `data` with whichever filter type gives the
+/// smallest sum of absolute differences, and prefixes the row with it.
+fn filter(data: &[u8], stride: usize, bits_per_pixel: usize) -> Vec<u8> {
+    let bpp = bits_per_pixel.div_ceil(8).max(1);
+    let mut out = Vec::with_capacity(data.len() + data.len() / stride.max(1));
+    let mut candidate = vec![0u8; stride];
+    let mut best = vec![0u8; stride];
+    let mut prev: Option<&[u8]> = None;
+    for row in data.chunks(stride.max(1)) {
+        let mut best_filter = 0;
+        let mut best_cost = u64::MAX;
+        for filter in 0..=4 {
+            for x in 0..stride {
+                let a = if x >= bpp { row[x - bpp] } else { 0 };
+                let b = prev.map_or(0, |p| p[x]);
+                let c = match prev {
+                    Some(p) if x >= bpp => p[x - bpp],
+                    _ => 0,
+                };
+                candidate[x] = row[x].wrapping_sub(predict(filter, a, b, c));
+            }
+            let cost = candidate
+                .iter()
+                .map(|&v| (v as i8).unsigned_abs() as u64)
+                .sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_filter = filter;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        out.push(best_filter);
+        out.extend(&best);
+        prev = Some(row);
+    }
+    out
+}
+
+/// The value filter type `filter` predicts from the bytes to the left (`a`),
+/// above (`b`) and above-left (`c`).
+fn predict(filter: u8, a: u8, b: u8, c: u8) -> u8 {
+    match filter {
+        1 => a,
+        2 => b,
+        3 => ((a as u16 + b as u16) / 2) as u8,
+        4 => paeth(a, b, c),
+        _ => 0,
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn copy_pixel(
+    src_row: &[u8],
+    src_x: usize,
+    dst: &mut [u8],
+    dst_row: usize,
+    dst_x: usize,
+    bpp: usize,
+) {
+    if bpp >= 8 {
+        let n = bpp / 8;
+        dst[dst_row + dst_x * n..dst_row + (dst_x + 1) * n]
+            .copy_from_slice(&src_row[src_x * n..(src_x + 1) * n]);
+        return;
+    }
+
+    let mask = (1u8 << bpp) - 1;
+    let src_shift = 8 - bpp - (src_x * bpp) % 8;
+    let value = (src_row[src_x * bpp / 8] >> src_shift) & mask;
+    let dst_shift = 8 - bpp - (dst_x * bpp) % 8;
+    let byte = &mut dst[dst_row + dst_x * bpp / 8];
+    *byte = (*byte & !(mask << dst_shift)) | (value << dst_shift);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::str::FromStr;
+
+    fn png_from_scanlines(width: u32, height: u32, interlace: u8, scanlines: &[u8]) -> Png {
+        let mut ihdr = width.to_be_bytes().to_vec();
+        ihdr.extend(height.to_be_bytes());
+        ihdr.extend([8, 0, 0, 0, interlace]);
+
+        let mut encoder = flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(scanlines).unwrap();
+        let idat = encoder.finish().unwrap();
+
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), ihdr),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), idat),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+        ])
+    }
+
+    #[test]
+    fn test_decode_filtered_rows() {
+        // Row 0 uses Sub, row 1 uses Up.
+        let png = png_from_scanlines(3, 2, 0, &[1, 10, 1, 1, 2, 5, 5, 5]);
+        let pixels = Pixels::decode(&png).unwrap();
+        assert_eq!(pixels.data, vec![10, 11, 12, 15, 16, 17]);
+    }
+
+    #[test]
+    fn test_decode_interlaced_matches_plain() {
+        // 2x2 grayscale image: pass 1 holds (0,0), pass 6 holds (1,0), pass 7 holds row 1.
+        let plain = png_from_scanlines(2, 2, 0, &[0, 1, 2, 0, 3, 4]);
+        let interlaced = png_from_scanlines(2, 2, 1, &[0, 1, 0, 2, 0, 3, 4]);
+        assert_eq!(
+            Pixels::decode(&plain).unwrap().data,
+            Pixels::decode(&interlaced).unwrap().data
+        );
+    }
+
+    #[test]
+    fn test_to_png_round_trip() {
+        let png = png_from_scanlines(3, 2, 0, &[1, 10, 1, 1, 2, 5, 5, 5]);
+        let pixels = Pixels::decode(&png).unwrap();
+        let decoded = Pixels::decode(&pixels.to_png()).unwrap();
+        assert_eq!(decoded.ihdr, pixels.ihdr);
+        assert_eq!(decoded.data, pixels.data);
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        let scanlines: Vec<u8> = (0..8u8)
+            .flat_map(|y| std::iter::once(0).chain((0..16).map(move |x| x * 3 + y)))
+            .collect();
+        let png = png_from_scanlines(16, 8, 0, &scanlines);
+        let pixels = Pixels::decode(&png).unwrap();
+
+        let filtered = inflate_image_data(
+            &Png::from_chunks(vec![Chunk::new(
+                ChunkType::from_str("IDAT").unwrap(),
+                pixels.compress(),
+            )]),
+            17 * 8,
+        )
+        .unwrap();
+        // a smooth gradient is best served by Sub or Up, not None
+        assert!(filtered.chunks(17).all(|row| row[0] != 0));
+        assert_eq!(unfilter(&filtered, 16, 8, 8).unwrap(), pixels.data);
+    }
+
+    #[test]
+    fn test_luma() {
+        let png = png_from_scanlines(3, 2, 0, &[1, 10, 1, 1, 2, 5, 5, 5]);
+        let pixels = Pixels::decode(&png).unwrap();
+        assert_eq!(pixels.luma(None).unwrap(), vec![10, 11, 12, 15, 16, 17]);
+    }
+
+    #[test]
+    fn test_decode_too_much_data() {
+        // 2x2 grayscale needs 6 bytes, but the stream inflates to far more.
+        let png = png_from_scanlines(2, 2, 0, &vec![0; 1 << 20]);
+        assert!(matches!(
+            Pixels::decode(&png),
+            Err(PixelError::Inflate(ZlibError::TooLarge(6)))
+        ));
+    }
+
+    #[test]
+    fn test_decode_missing_idat() {
+        let mut png = png_from_scanlines(1, 1, 0, &[0, 0]);
+        png.remove_chunk("IDAT").unwrap();
+        assert!(Pixels::decode(&png).is_err());
+    }
+}
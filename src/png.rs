@@ -1,6 +1,8 @@
-use bytes::Buf;
+use std::io::{BufRead, Write};
 
-use crate::chunk::Chunk;
+use crate::chunk::{self, split_header, Chunk};
+use crate::chunk_type::ChunkType;
+use crate::stream::ChunkReader;
 use crate::Result;
 
 #[derive(Debug, thiserror::Error)]
@@ -13,6 +15,51 @@ pub enum PngError {
     Corrupted,
 }
 
+/// A problem found by [`Png::parse_lenient`]. Offsets are from the start of
+/// the file.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ParseIssue {
+    #[error("chunk {index} ({chunk_type}) at offset {offset}: CRC mismatch, stored {stored:08x}, computed {computed:08x}")]
+    CrcMismatch {
+        index: usize,
+        chunk_type: String,
+        offset: usize,
+        stored: u32,
+        computed: u32,
+    },
+    #[error("offset {offset}: chunk length {len} runs past the end of the file")]
+    BadLength { offset: usize, len: usize },
+    #[error("offset {offset}: invalid chunk type {chunk_type:?}")]
+    BadChunkType { offset: usize, chunk_type: String },
+    #[error("{len} bytes of trailing data at offset {offset}")]
+    TrailingData { offset: usize, len: usize },
+    #[error("missing IHDR chunk")]
+    MissingIhdr,
+    #[error("missing IEND chunk")]
+    MissingIend,
+    #[error("chunk {index} ({chunk_type}): {reason}")]
+    Misordered {
+        index: usize,
+        chunk_type: String,
+        reason: &'static str,
+    },
+}
+
+impl ParseIssue {
+    /// Whether writing the parsed `Png` back out resolves the issue: CRCs are
+    /// recomputed and unreadable or trailing bytes are dropped.
+    pub fn is_fixable(&self) -> bool {
+        matches!(
+            self,
+            ParseIssue::CrcMismatch { .. }
+                | ParseIssue::BadLength { .. }
+                | ParseIssue::BadChunkType { .. }
+                | ParseIssue::TrailingData { .. }
+        )
+    }
+}
+
+#[derive(Clone)]
 pub struct Png {
     chunks: Vec<Chunk>,
 }
@@ -28,6 +75,56 @@ impl Png {
         self.chunks.push(chunk)
     }
 
+    pub fn append_chunks(&mut self, chunks: impl IntoIterator<Item = Chunk>) {
+        self.chunks.extend(chunks)
+    }
+
+    pub fn insert_chunk_at(&mut self, index: usize, chunk: Chunk) {
+        self.chunks.insert(index, chunk)
+    }
+
+    /// Inserts `chunk` right before the first chunk of `chunk_type`.
+    pub fn insert_before(&mut self, chunk_type: &str, chunk: Chunk) -> Result<()> {
+        let idx = self
+            .chunks
+            .iter()
+            .position(|c| c.chunk_type().to_string() == chunk_type)
+            .ok_or(PngError::ChunkNotFound)?;
+        self.chunks.insert(idx, chunk);
+        Ok(())
+    }
+
+    /// Inserts `chunks` right before IEND, where the PNG spec requires
+    /// ancillary chunks to go, or at the end if there is no IEND.
+    pub fn insert_before_iend(&mut self, chunks: impl IntoIterator<Item = Chunk>) {
+        let idx = self
+            .chunks
+            .iter()
+            .position(|c| *c.chunk_type() == ChunkType::IEND)
+            .unwrap_or(self.chunks.len());
+        self.chunks.splice(idx..idx, chunks);
+    }
+
+    /// Indices at which an ancillary chunk can be inserted without breaking
+    /// the file: after IHDR, before IEND, and not between two IDAT chunks.
+    pub fn insertion_points(&self) -> Vec<usize> {
+        let is = |idx: usize, chunk_type: ChunkType| {
+            self.chunks
+                .get(idx)
+                .is_some_and(|c| *c.chunk_type() == chunk_type)
+        };
+        let first = if is(0, ChunkType::IHDR) { 1 } else { 0 };
+        let last = self
+            .chunks
+            .iter()
+            .position(|c| *c.chunk_type() == ChunkType::IEND)
+            .unwrap_or(self.chunks.len());
+
+        (first..=last.max(first))
+            .filter(|&idx| !(idx > 0 && is(idx - 1, ChunkType::IDAT) && is(idx, ChunkType::IDAT)))
+            .collect()
+    }
+
     pub fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk> {
         let found = self
             .chunks
@@ -41,6 +138,22 @@ impl Png {
         }
     }
 
+    /// Removes every chunk for which `predicate` returns true and returns
+    /// them. `predicate` is called once per chunk, in file order.
+    pub fn remove_chunks_where(&mut self, predicate: impl FnMut(&Chunk) -> bool) -> Vec<Chunk> {
+        let (removed, kept) = std::mem::take(&mut self.chunks)
+            .into_iter()
+            .partition(predicate);
+        self.chunks = kept;
+        removed
+    }
+
+    /// Keeps only the chunks for which `predicate` returns true and returns
+    /// the others, like [`Png::remove_chunks_where`] with the test inverted.
+    pub fn retain_chunks(&mut self, mut predicate: impl FnMut(&Chunk) -> bool) -> Vec<Chunk> {
+        self.remove_chunks_where(|c| !predicate(c))
+    }
+
     pub fn header(&self) -> &[u8; 8] {
         &Self::STANDARD_HEADER
     }
@@ -49,18 +162,188 @@ impl Png {
         &self.chunks
     }
 
+    /// Every chunk with the file offset of its length field.
+    pub fn chunks_with_offsets(&self) -> impl Iterator<Item = (u64, &Chunk)> {
+        self.chunks
+            .iter()
+            .scan(Self::STANDARD_HEADER.len() as u64, |offset, chunk| {
+                let start = *offset;
+                // length + type + data + crc
+                *offset += 4 + 4 + chunk.length() as u64 + 4;
+                Some((start, chunk))
+            })
+    }
+
+    pub fn chunks_mut(&mut self) -> &mut [Chunk] {
+        &mut self.chunks
+    }
+
     pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
         self.chunks
             .iter()
             .find(|c| c.chunk_type().to_string() == chunk_type)
     }
 
-    pub fn as_bytes(&self) -> Vec<u8> {
-        let mut header_bytes = Self::STANDARD_HEADER.to_vec();
-        let chunk_bytes: Vec<u8> = self.chunks.iter().flat_map(|c| c.as_bytes()).collect();
-        header_bytes.extend(chunk_bytes);
+    /// Every chunk of `chunk_type`, in file order.
+    pub fn chunks_by_type<'a>(&'a self, chunk_type: &'a str) -> impl Iterator<Item = &'a Chunk> {
+        self.chunks
+            .iter()
+            .filter(move |c| c.chunk_type().to_string() == chunk_type)
+    }
+
+    pub fn chunk_by_type_mut(&mut self, chunk_type: &str) -> Option<&mut Chunk> {
+        self.chunks
+            .iter_mut()
+            .find(|c| c.chunk_type().to_string() == chunk_type)
+    }
+
+    /// Parses as much of `bytes` as possible, collecting problems instead of
+    /// failing on the first one. Chunks with a bad CRC are kept; parsing stops
+    /// at a chunk that runs past the end of the file, or at the first bytes
+    /// after IEND that do not read as a chunk. Only a wrong signature is an
+    /// error.
+    pub fn parse_lenient(bytes: &[u8]) -> std::result::Result<(Png, Vec<ParseIssue>), PngError> {
+        if !bytes.starts_with(&Self::STANDARD_HEADER) {
+            return Err(PngError::WrongHeader);
+        }
+
+        let mut chunks = vec![];
+        let mut issues = vec![];
+        let mut after_iend = false;
+        let mut idx = Self::STANDARD_HEADER.len();
+        while idx < bytes.len() {
+            let rest = bytes.len() - idx;
+            let len = bytes
+                .get(idx..idx + 4)
+                .map(|len| u32::from_be_bytes(len.try_into().unwrap()) as usize);
+            let type_bytes: Option<[u8; 4]> = bytes
+                .get(idx + 4..idx + 8)
+                .map(|type_bytes| type_bytes.try_into().unwrap());
+            let chunk_type = type_bytes.and_then(|type_bytes| ChunkType::try_from(type_bytes).ok());
+            let fits = len.is_some_and(|len| len <= Chunk::MAX_LENGTH && 4 + 4 + len + 4 <= rest);
+
+            // Whatever follows IEND and does not read as a chunk is garbage
+            // appended to the file.
+            if len.is_none() || (after_iend && !(fits && chunk_type.is_some())) {
+                issues.push(ParseIssue::TrailingData {
+                    offset: idx,
+                    len: rest,
+                });
+                break;
+            }
+            let len = len.unwrap();
+            if !fits {
+                issues.push(ParseIssue::BadLength { offset: idx, len });
+                break;
+            }
+
+            let data = &bytes[idx + 8..idx + 8 + len];
+            let stored =
+                u32::from_be_bytes(bytes[idx + 8 + len..idx + 12 + len].try_into().unwrap());
+            match chunk_type {
+                Some(chunk_type) => {
+                    after_iend |= chunk_type == ChunkType::IEND;
+                    let chunk = Chunk::new(chunk_type, data.to_vec());
+                    if chunk.crc() != stored {
+                        issues.push(ParseIssue::CrcMismatch {
+                            index: chunks.len(),
+                            chunk_type: chunk.chunk_type().to_string(),
+                            offset: idx,
+                            stored,
+                            computed: chunk.crc(),
+                        });
+                    }
+                    chunks.push(chunk);
+                }
+                None => issues.push(ParseIssue::BadChunkType {
+                    offset: idx,
+                    chunk_type: String::from_utf8_lossy(&type_bytes.unwrap()).to_string(),
+                }),
+            }
+            idx += 4 + 4 + len + 4;
+        }
+
+        let png = Png { chunks };
+        issues.extend(png.order_issues());
+        Ok((png, issues))
+    }
+
+    /// Violations of the critical chunk order: IHDR first, IEND last, PLTE
+    /// before the image data, and IDAT chunks consecutive.
+    fn order_issues(&self) -> Vec<ParseIssue> {
+        let types: Vec<ChunkType> = self.chunks.iter().map(|c| *c.chunk_type()).collect();
+        let mut issues = vec![];
+        let mut misordered = |index: usize, reason| {
+            issues.push(ParseIssue::Misordered {
+                index,
+                chunk_type: types[index].to_string(),
+                reason,
+            })
+        };
 
-        header_bytes
+        let first_idat = types.iter().position(|&t| t == ChunkType::IDAT);
+        let last_idat = types.iter().rposition(|&t| t == ChunkType::IDAT);
+        for (index, &chunk_type) in types.iter().enumerate() {
+            match chunk_type {
+                ChunkType::IHDR if index != 0 => misordered(index, "IHDR must be the first chunk"),
+                ChunkType::IEND if index != types.len() - 1 => {
+                    misordered(index, "IEND must be the last chunk")
+                }
+                ChunkType::PLTE if first_idat.is_some_and(|first| index > first) => {
+                    misordered(index, "PLTE must come before the image data")
+                }
+                _ => {}
+            }
+        }
+        if let (Some(first), Some(last)) = (first_idat, last_idat) {
+            if let Some(gap) = (first..last).find(|&idx| types[idx] != ChunkType::IDAT) {
+                misordered(gap, "IDAT chunks must be consecutive");
+            }
+        }
+
+        if !types.contains(&ChunkType::IHDR) {
+            issues.insert(0, ParseIssue::MissingIhdr);
+        }
+        if !types.contains(&ChunkType::IEND) {
+            issues.push(ParseIssue::MissingIend);
+        }
+        issues
+    }
+
+    /// Parses a PNG chunk by chunk from `reader`, without buffering the
+    /// whole file. Use [`ChunkReader`] directly to skip chunk data.
+    pub fn from_reader(reader: impl BufRead) -> Result<Png> {
+        let mut reader = ChunkReader::new(reader)?;
+        let mut chunks = vec![];
+        while let Some(chunk) = reader.next_chunk()? {
+            chunks.push(chunk);
+        }
+        Ok(Png { chunks })
+    }
+
+    /// Writes the file chunk by chunk, without building it in memory first.
+    pub fn write_to(&self, mut writer: impl Write) -> std::io::Result<()> {
+        writer.write_all(&Self::STANDARD_HEADER)?;
+        for chunk in &self.chunks {
+            chunk.write_to(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Length of [`Png::as_bytes`], without building it.
+    pub fn byte_len(&self) -> usize {
+        let chunks = self
+            .chunks
+            .iter()
+            .map(|c| c.data().len() + 12)
+            .sum::<usize>();
+        Self::STANDARD_HEADER.len() + chunks
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.byte_len());
+        self.write_to(&mut bytes).unwrap();
+        bytes
     }
 }
 
@@ -88,26 +371,19 @@ impl TryFrom<&[u8]> for Png {
         }
 
         // Parse chunks
-        let mut idx = Self::STANDARD_HEADER.len();
+        let mut rest = &value[Self::STANDARD_HEADER.len()..];
         let mut chunks = vec![];
-        while idx < total_len {
-            let chunk_data_len_bytes = value[idx..(idx + 4)].try_into().unwrap();
-            let chunk_data_len = u32::from_be_bytes(chunk_data_len_bytes);
-            let chunk_bytes_len = 4 + 4 + chunk_data_len + 4;
-            if idx + chunk_bytes_len as usize > total_len {
-                println!("?");
-                return Err(PngError::Corrupted);
-            }
-
-            let chunk_bytes = &value[idx..(idx + chunk_bytes_len as usize)];
-            if let Ok(chunk) = Chunk::try_from(&chunk_bytes.to_vec()) {
-                chunks.push(chunk);
-            } else {
-                println!("Chunk from fails");
+        while !rest.is_empty() {
+            let (data_len, _, _) = split_header(rest).map_err(|_| PngError::Corrupted)?;
+            let chunk_len = 4 + 4 + data_len + 4;
+            if chunk_len > rest.len() {
                 return Err(PngError::Corrupted);
             }
 
-            idx += chunk_bytes_len as usize;
+            let (chunk_bytes, tail) = rest.split_at(chunk_len);
+            let chunk = chunk::parse(chunk_bytes).map_err(|_| PngError::Corrupted)?;
+            chunks.push(chunk);
+            rest = tail;
         }
 
         Ok(Png { chunks })
@@ -115,6 +391,11 @@ impl TryFrom<&[u8]> for Png {
 }
 
 #[cfg(test)]
+#[allow(
+    unused_imports,
+    clippy::vec_init_then_push,
+    clippy::iter_cloned_collect
+)]
 mod tests {
     use super::*;
     use crate::chunk::Chunk;
@@ -236,6 +517,26 @@ mod tests {
         assert_eq!(&chunk.data_as_string().unwrap(), "Message");
     }
 
+    #[test]
+    fn test_insert_before_iend() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("IEND", "").unwrap());
+        png.insert_before_iend(vec![
+            chunk_from_strings("TeSt", "one").unwrap(),
+            chunk_from_strings("TeSt", "two").unwrap(),
+        ]);
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["FrSt", "miDl", "LASt", "TeSt", "TeSt", "IEND"]);
+
+        let mut png = testing_png();
+        png.insert_before_iend([chunk_from_strings("TeSt", "one").unwrap()]);
+        assert_eq!(&png.chunks()[3].chunk_type().to_string(), "TeSt");
+    }
+
     #[test]
     fn test_remove_chunk() {
         let mut png = testing_png();
@@ -245,6 +546,105 @@ mod tests {
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn test_remove_chunks_where() {
+        let mut png = testing_png();
+        png.append_chunks(vec![
+            chunk_from_strings("miDl", "second").unwrap(),
+            chunk_from_strings("miDl", "third").unwrap(),
+        ]);
+        let mut seen = 0;
+        let removed = png.remove_chunks_where(|c| {
+            let is_midl = c.chunk_type().to_string() == "miDl";
+            seen += is_midl as usize;
+            is_midl && seen > 1
+        });
+        let data: Vec<&[u8]> = removed.iter().map(|c| c.data()).collect();
+        assert_eq!(data, [&b"second"[..], &b"third"[..]]);
+        assert_eq!(png.chunks().len(), 3);
+        assert!(png.remove_chunks_where(|_| false).is_empty());
+    }
+
+    #[test]
+    fn test_chunks_with_offsets() {
+        let png = testing_png();
+        let offsets: Vec<u64> = png
+            .chunks_with_offsets()
+            .map(|(offset, _)| offset)
+            .collect();
+        assert_eq!(offsets[0], 8);
+        for (idx, chunk) in png.chunks().iter().enumerate().skip(1) {
+            let previous = &png.chunks()[idx - 1];
+            assert_eq!(
+                offsets[idx],
+                offsets[idx - 1] + previous.length() as u64 + 12
+            );
+            let offset = offsets[idx] as usize;
+            assert_eq!(
+                &png.as_bytes()[offset + 4..offset + 8],
+                &chunk.chunk_type().bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_retain_chunks() {
+        let mut png = testing_png();
+        let removed = png.retain_chunks(|c| c.chunk_type().is_critical());
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["FrSt", "LASt"]);
+        assert_eq!(removed[0].chunk_type().to_string(), "miDl");
+    }
+
+    #[test]
+    fn test_insert_chunk_at() {
+        let mut png = testing_png();
+        png.insert_chunk_at(1, chunk_from_strings("TeSt", "Message").unwrap());
+        assert_eq!(&png.chunks()[1].chunk_type().to_string(), "TeSt");
+        assert_eq!(png.chunks().len(), 4);
+    }
+
+    #[test]
+    fn test_insertion_points() {
+        let png = Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("gAMA", "gamma").unwrap(),
+            chunk_from_strings("IDAT", "data").unwrap(),
+            chunk_from_strings("IDAT", "data").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]);
+        assert_eq!(png.insertion_points(), vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn test_insert_before() {
+        let mut png = testing_png();
+        png.insert_before("miDl", chunk_from_strings("teSt", "new").unwrap())
+            .unwrap();
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["FrSt", "teSt", "miDl", "LASt"]);
+
+        let chunk = chunk_from_strings("teSt", "new").unwrap();
+        assert!(png.insert_before("IDAT", chunk).is_err());
+    }
+
+    #[test]
+    fn test_chunk_by_type_mut() {
+        let mut png = testing_png();
+        let chunk = png.chunk_by_type_mut("miDl").unwrap();
+        chunk.set_data(b"Changed".to_vec()).unwrap();
+        let chunk = png.chunk_by_type("miDl").unwrap();
+        assert_eq!(&chunk.data_as_string().unwrap(), "Changed");
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
@@ -260,6 +660,149 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_byte_len() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.byte_len(), PNG_FILE.len());
+        assert_eq!(png.byte_len(), png.as_bytes().len());
+    }
+
+    fn lenient_testing_chunks() -> Vec<Chunk> {
+        vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IDAT", "data").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test_parse_lenient_valid() {
+        let bytes = Png::from_chunks(lenient_testing_chunks()).as_bytes();
+        let (png, issues) = Png::parse_lenient(&bytes).unwrap();
+        assert_eq!(png.chunks().len(), 3);
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_parse_lenient_crc_and_trailing_data() {
+        let mut bytes = Png::from_chunks(lenient_testing_chunks()).as_bytes();
+        // last CRC byte of IHDR: signature + length + type + "header"
+        bytes[8 + 4 + 4 + 6 + 3] ^= 0xff;
+        bytes.extend(b"garbage");
+
+        let (png, issues) = Png::parse_lenient(&bytes).unwrap();
+        assert_eq!(png.chunks().len(), 3);
+        assert!(matches!(
+            issues[0],
+            ParseIssue::CrcMismatch { index: 0, .. }
+        ));
+        assert_eq!(
+            issues[1],
+            ParseIssue::TrailingData {
+                offset: bytes.len() - 7,
+                len: 7
+            }
+        );
+        assert!(issues.iter().all(ParseIssue::is_fixable));
+        assert!(Png::try_from(&png.as_bytes()[..]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_lenient_chunk_after_iend() {
+        let mut chunks = lenient_testing_chunks();
+        chunks.push(chunk_from_strings("ruSt", "message").unwrap());
+        let bytes = Png::from_chunks(chunks).as_bytes();
+        let (png, issues) = Png::parse_lenient(&bytes).unwrap();
+
+        assert_eq!(png.chunks().len(), 4);
+        assert!(matches!(
+            issues[..],
+            [ParseIssue::Misordered { index: 2, .. }]
+        ));
+    }
+
+    #[test]
+    fn test_parse_lenient_truncated_and_misordered() {
+        let mut chunks = lenient_testing_chunks();
+        chunks.swap(0, 1);
+        let bytes = Png::from_chunks(chunks).as_bytes();
+        let (png, issues) = Png::parse_lenient(&bytes[..bytes.len() - 2]).unwrap();
+
+        assert_eq!(png.chunks().len(), 2);
+        assert!(matches!(issues[0], ParseIssue::BadLength { .. }));
+        assert!(matches!(issues[1], ParseIssue::Misordered { index: 1, .. }));
+        assert_eq!(issues[2], ParseIssue::MissingIend);
+    }
+
+    #[test]
+    fn test_chunks_by_type() {
+        let mut png = testing_png();
+        png.append_chunks(vec![
+            chunk_from_strings("miDl", "second").unwrap(),
+            chunk_from_strings("miDl", "third").unwrap(),
+        ]);
+        let data: Vec<&[u8]> = png.chunks_by_type("miDl").map(|c| c.data()).collect();
+        assert_eq!(
+            data,
+            [&b"I am another chunk"[..], &b"second"[..], &b"third"[..]]
+        );
+    }
+
+    #[test]
+    fn test_reader_round_trip() {
+        let png = testing_png();
+        let mut bytes = vec![];
+        png.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes, png.as_bytes());
+
+        let read = Png::from_reader(&bytes[..]).unwrap();
+        assert_eq!(read.as_bytes(), bytes);
+    }
+
+    /// Every prefix of a real file, a few thousand corrupted copies of it and
+    /// random byte streams must give errors, never panics.
+    #[test]
+    fn test_malformed_input_does_not_panic() {
+        use rand::{rngs::StdRng, RngExt, SeedableRng};
+
+        let parse_all = |bytes: &[u8]| {
+            let _ = Png::try_from(bytes);
+            let _ = Png::parse_lenient(bytes);
+            let _ = Png::from_reader(bytes);
+        };
+
+        for len in 0..PNG_FILE.len() {
+            parse_all(&PNG_FILE[..len]);
+        }
+
+        let mut rng = StdRng::seed_from_u64(0x706e_676d);
+        for _ in 0..2000 {
+            let mut bytes = PNG_FILE.to_vec();
+            for _ in 0..rng.random_range(1..8) {
+                let idx = rng.random_range(0..bytes.len());
+                bytes[idx] = rng.random();
+            }
+            parse_all(&bytes);
+        }
+        for _ in 0..2000 {
+            let len = rng.random_range(0..64);
+            let mut bytes = Png::STANDARD_HEADER.to_vec();
+            bytes.extend((0..len).map(|_| rng.random::<u8>()));
+            parse_all(&bytes);
+        }
+    }
+
+    #[test]
+    fn test_huge_length_is_error() {
+        let mut bytes = Png::STANDARD_HEADER.to_vec();
+        bytes.extend(u32::MAX.to_be_bytes());
+        bytes.extend(b"ruSt");
+        assert!(Png::try_from(&bytes[..]).is_err());
+        assert!(Png::from_reader(&bytes[..]).is_err());
+        let (_, issues) = Png::parse_lenient(&bytes).unwrap();
+        assert!(matches!(issues[0], ParseIssue::BadLength { .. }));
+    }
+
     #[test]
     fn test_png_trait_impls() {
         let chunk_bytes: Vec<u8> = testing_chunks()
@@ -0,0 +1,123 @@
+//! Several length-prefixed records packed into the data of one chunk.
+//!
+//! Layout: the `MAGIC` marker followed by any number of records, each a
+//! big-endian `u32` length and that many bytes. Chunk data without the marker
+//! is treated as a single record, so plain messages can be extended in place.
+
+#[derive(Debug, thiserror::Error)]
+pub enum RecordsError {
+    #[error("record {0} is truncated")]
+    Truncated(usize),
+    #[error("record {0} not found")]
+    NotFound(usize),
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Records {
+    records: Vec<Vec<u8>>,
+}
+
+impl Records {
+    pub const MAGIC: [u8; 4] = *b"PMR\x01";
+
+    pub fn parse(data: &[u8]) -> std::result::Result<Records, RecordsError> {
+        let Some(mut rest) = data.strip_prefix(&Self::MAGIC[..]) else {
+            return Ok(Records {
+                records: vec![data.to_vec()],
+            });
+        };
+
+        let mut records = vec![];
+        while !rest.is_empty() {
+            let idx = records.len();
+            if rest.len() < 4 {
+                return Err(RecordsError::Truncated(idx));
+            }
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            // On 32-bit targets a crafted length overflows the end offset.
+            let end = len
+                .checked_add(4)
+                .filter(|&end| end <= rest.len())
+                .ok_or(RecordsError::Truncated(idx))?;
+            records.push(rest[4..end].to_vec());
+            rest = &rest[end..];
+        }
+
+        Ok(Records { records })
+    }
+
+    pub fn push(&mut self, record: Vec<u8>) {
+        self.records.push(record)
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn get(&self, idx: usize) -> std::result::Result<&[u8], RecordsError> {
+        self.records
+            .get(idx)
+            .map(|r| &r[..])
+            .ok_or(RecordsError::NotFound(idx))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[u8]> {
+        self.records.iter().map(|r| &r[..])
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = Self::MAGIC.to_vec();
+        for record in &self.records {
+            bytes.extend((record.len() as u32).to_be_bytes());
+            bytes.extend(record);
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_data_is_single_record() {
+        let records = Records::parse(b"hello").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records.get(0).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut records = Records::parse(b"first").unwrap();
+        records.push(b"second".to_vec());
+        records.push(vec![]);
+
+        let parsed = Records::parse(&records.as_bytes()).unwrap();
+        assert_eq!(parsed, records);
+        assert_eq!(parsed.get(1).unwrap(), b"second");
+        assert!(parsed.get(3).is_err());
+    }
+
+    #[test]
+    fn test_truncated_record() {
+        let mut bytes = Records::MAGIC.to_vec();
+        bytes.extend(10u32.to_be_bytes());
+        bytes.extend(b"short");
+        assert!(Records::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_record_length_overflow() {
+        let mut bytes = Records::MAGIC.to_vec();
+        bytes.extend(u32::MAX.to_be_bytes());
+        bytes.extend(b"short");
+        assert!(matches!(
+            Records::parse(&bytes),
+            Err(RecordsError::Truncated(0))
+        ));
+    }
+}
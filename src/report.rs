@@ -0,0 +1,71 @@
+//! Machine-readable description of a PNG file.
+
+use serde::Serialize;
+
+use crate::chunk::Chunk;
+use crate::png::Png;
+use crate::text::TextChunk;
+
+/// Every chunk of a file with its position, for JSON output.
+#[derive(Serialize)]
+pub struct PngReport<'a> {
+    /// Size of the encoded file in bytes.
+    pub size: u64,
+    pub chunks: Vec<ChunkReport<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct ChunkReport<'a> {
+    pub index: usize,
+    /// Offset of the chunk's length field from the start of the file.
+    pub offset: u64,
+    /// Whether the chunk type is standard or registered.
+    pub standard: bool,
+    #[serde(flatten)]
+    pub chunk: &'a Chunk,
+    /// Keyword and text of tEXt, zTXt and iTXt chunks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<TextChunk>,
+}
+
+impl<'a> PngReport<'a> {
+    pub fn new(png: &'a Png) -> PngReport<'a> {
+        let chunks = png
+            .chunks_with_offsets()
+            .enumerate()
+            .map(|(index, (offset, chunk))| ChunkReport {
+                index,
+                offset,
+                standard: chunk.chunk_type().is_standard(),
+                chunk,
+                text: TextChunk::parse(chunk).and_then(|text| text.ok()),
+            })
+            .collect();
+        PngReport {
+            size: png.byte_len() as u64,
+            chunks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_offsets() {
+        let chunk = |chunk_type: &str, data: &[u8]| {
+            Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+        };
+        let png = Png::from_chunks(vec![chunk("IHDR", &[0; 13]), chunk("ruSt", b"hi")]);
+        let report = PngReport::new(&png);
+
+        assert_eq!(report.size, png.as_bytes().len() as u64);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["chunks"][1]["offset"], 8 + 12 + 13);
+        assert_eq!(json["chunks"][1]["type"], "ruSt");
+        assert_eq!(json["chunks"][1]["standard"], false);
+    }
+}
@@ -0,0 +1,73 @@
+//! Fast searches over arbitrary byte buffers (disk images, memory dumps, ...)
+//! for PNG signatures, chunk headers and known payload formats. Searches use
+//! `memchr`'s SIMD substring finder so they run at close to memory bandwidth.
+
+use memchr::memmem;
+
+use crate::magic;
+use crate::png::Png;
+
+/// Offsets of every PNG signature in `haystack`.
+pub fn png_signatures(haystack: &[u8]) -> impl Iterator<Item = usize> + '_ {
+    memmem::find_iter(haystack, &Png::STANDARD_HEADER)
+}
+
+/// Offsets of plausible chunk headers of `chunk_type` in `haystack`: the type
+/// must be preceded by a length that fits the rest of the buffer. Offsets point
+/// at the length field.
+pub fn chunk_headers<'a>(
+    haystack: &'a [u8],
+    chunk_type: &'a [u8; 4],
+) -> impl Iterator<Item = usize> + 'a {
+    memmem::find_iter(haystack, chunk_type).filter_map(move |pos| {
+        let start = pos.checked_sub(4)?;
+        let len = u32::from_be_bytes(haystack[start..pos].try_into().unwrap()) as usize;
+        // length + type + data + crc
+        (start + 4 + 4 + len + 4 <= haystack.len()).then_some(start)
+    })
+}
+
+/// Every occurrence of a known file signature in `haystack`, ordered by offset.
+pub fn magic_hits(haystack: &[u8]) -> Vec<(usize, &'static str)> {
+    let mut hits: Vec<(usize, &'static str)> = magic::MAGICS
+        .iter()
+        .flat_map(|(name, signature)| {
+            memmem::find_iter(haystack, signature).map(move |pos| (pos, *name))
+        })
+        .collect();
+    hits.sort();
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png_signatures() {
+        let mut haystack = b"garbage".to_vec();
+        haystack.extend(Png::STANDARD_HEADER);
+        haystack.extend(b"more garbage");
+        haystack.extend(Png::STANDARD_HEADER);
+        assert_eq!(png_signatures(&haystack).collect::<Vec<_>>(), vec![7, 27]);
+    }
+
+    #[test]
+    fn test_chunk_headers() {
+        let mut haystack = b"xxIEND".to_vec();
+        haystack.extend(0u32.to_be_bytes());
+        haystack.extend(b"IEND");
+        haystack.extend([0xae, 0x42, 0x60, 0x82]);
+        // The first "IEND" is not preceded by a length.
+        assert_eq!(
+            chunk_headers(&haystack, b"IEND").collect::<Vec<_>>(),
+            vec![6]
+        );
+    }
+
+    #[test]
+    fn test_magic_hits() {
+        let haystack = b"....%PDF-1.4....PK\x03\x04";
+        assert_eq!(magic_hits(haystack), vec![(4, "pdf"), (16, "zip")]);
+    }
+}
@@ -0,0 +1,104 @@
+//! Perceptual hashing to recognise resized or recompressed copies of an image.
+
+use crate::pixels::{PixelError, Pixels};
+use crate::png::Png;
+
+/// 64-bit average hash: the image is reduced to 8x8 grey blocks and each bit
+/// records whether a block is brighter than the mean. Scaled or re-encoded
+/// copies of an image have hashes within a few bits of each other.
+pub fn average_hash(png: &Png) -> std::result::Result<u64, PixelError> {
+    let pixels = Pixels::decode(png)?;
+    let palette = png.chunk_by_type("PLTE").map(|c| c.data());
+    let luma = pixels.luma(palette)?;
+
+    let width = pixels.ihdr.width as usize;
+    let height = pixels.ihdr.height as usize;
+    if width == 0 || height == 0 {
+        return Ok(0);
+    }
+    if Some(luma.len()) != width.checked_mul(height) {
+        return Err(PixelError::Truncated);
+    }
+
+    let mut blocks = [0f64; 64];
+    for (by, row) in blocks.chunks_mut(8).enumerate() {
+        let (y0, y1) = span(by, height);
+        for (bx, block) in row.iter_mut().enumerate() {
+            let (x0, x1) = span(bx, width);
+            let sum: u64 = (y0..y1)
+                .flat_map(|y| luma[y * width + x0..y * width + x1].iter())
+                .map(|&v| v as u64)
+                .sum();
+            *block = sum as f64 / ((y1 - y0) * (x1 - x0)) as f64;
+        }
+    }
+
+    let mean = blocks.iter().sum::<f64>() / 64.0;
+    Ok(blocks
+        .iter()
+        .enumerate()
+        .filter(|(_, &v)| v > mean)
+        .fold(0, |hash, (idx, _)| hash | (1 << idx)))
+}
+
+/// Number of differing bits between two hashes.
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Pixel range covered by block `idx` of 8 along an axis of `len` pixels.
+fn span(idx: usize, len: usize) -> (usize, usize) {
+    let start = (idx * len / 8).min(len - 1);
+    let end = ((idx + 1) * len / 8).max(start + 1);
+    (start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32, seed: u8) -> Png {
+        let data: Vec<u8> = (0..height)
+            .flat_map(|y| {
+                (0..width).map(move |x| ((x * 255 / width) as u8) ^ ((y % 2) as u8 * seed))
+            })
+            .collect();
+        Pixels {
+            ihdr: crate::ihdr::Ihdr {
+                width,
+                height,
+                bit_depth: 8,
+                color_type: 0,
+                compression_method: 0,
+                filter_method: 0,
+                interlace_method: 0,
+            },
+            data,
+        }
+        .to_png()
+    }
+
+    #[test]
+    fn test_resized_copy_is_close() {
+        let original = average_hash(&gradient(64, 64, 0)).unwrap();
+        let resized = average_hash(&gradient(16, 16, 0)).unwrap();
+        assert!(distance(original, resized) <= 4);
+    }
+
+    #[test]
+    fn test_different_image_is_far() {
+        let horizontal = average_hash(&gradient(32, 32, 0)).unwrap();
+        let inverted = !horizontal;
+        assert_eq!(distance(horizontal, inverted), 64);
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let png = gradient(8, 8, 0);
+        let mut ihdr = crate::ihdr::Ihdr::from_png(&png).unwrap();
+        ihdr.bit_depth = 0;
+        let mut chunks = png.chunks().to_vec();
+        chunks[0] = ihdr.to_chunk();
+        assert!(average_hash(&Png::from_chunks(chunks)).is_err());
+    }
+}
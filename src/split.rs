@@ -0,0 +1,196 @@
+//! Payloads split across several chunks of the same type. Every part starts
+//! with a header naming its position, so the parts can be put back together
+//! whatever order they end up in:
+//!
+//! ```text
+//! "SPLT" | index (u32) | count (u32) | payload bytes
+//! ```
+
+use std::borrow::Cow;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+
+const MAGIC: &[u8; 4] = b"SPLT";
+pub const HEADER_LEN: usize = MAGIC.len() + 4 + 4;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SplitError {
+    #[error("part {0} is missing")]
+    MissingPart(u32),
+    #[error("part {0} appears more than once")]
+    DuplicatePart(u32),
+    /// The part counts of each message, when the chunks hold several.
+    #[error(
+        "the chunks hold {} split messages, of {} parts, and only one can be decoded",
+        .0.len(),
+        list(.0)
+    )]
+    SeveralMessages(Vec<u32>),
+}
+
+fn list(counts: &[u32]) -> String {
+    let counts: Vec<String> = counts.iter().map(u32::to_string).collect();
+    match counts.split_last() {
+        Some((last, rest)) if !rest.is_empty() => format!("{} and {}", rest.join(", "), last),
+        _ => counts.concat(),
+    }
+}
+
+/// Splits `payload` into chunks of `chunk_type` holding at most `max_len`
+/// bytes of data each, header included. `max_len` must exceed [`HEADER_LEN`].
+pub fn split(chunk_type: &ChunkType, payload: &[u8], max_len: usize) -> Vec<Chunk> {
+    assert!(max_len > HEADER_LEN, "parts must have room for data");
+    let parts: Vec<&[u8]> = match payload.is_empty() {
+        true => vec![&[]],
+        false => payload.chunks(max_len - HEADER_LEN).collect(),
+    };
+    let count = parts.len() as u32;
+    parts
+        .into_iter()
+        .enumerate()
+        .map(|(index, part)| {
+            let mut data = Vec::with_capacity(HEADER_LEN + part.len());
+            data.extend(MAGIC);
+            data.extend((index as u32).to_be_bytes());
+            data.extend(count.to_be_bytes());
+            data.extend(part);
+            Chunk::new(*chunk_type, data)
+        })
+        .collect()
+}
+
+/// `payload` as it should be stored in a single chunk: unchanged, unless it
+/// starts with the part header and would be mistaken for a part, in which
+/// case it becomes the only part of itself.
+pub fn escape(payload: &[u8]) -> Cow<'_, [u8]> {
+    if !payload.starts_with(MAGIC) {
+        return Cow::Borrowed(payload);
+    }
+    let mut data = Vec::with_capacity(HEADER_LEN + payload.len());
+    data.extend(MAGIC);
+    data.extend(0u32.to_be_bytes());
+    data.extend(1u32.to_be_bytes());
+    data.extend(payload);
+    Cow::Owned(data)
+}
+
+/// Reassembles the payload of parts produced by [`split`], or returns `None`
+/// if `chunks` are not all parts. Parts of several messages, told apart by
+/// their first parts and their part counts, are an error.
+pub fn join<'a>(
+    chunks: impl IntoIterator<Item = &'a Chunk>,
+) -> Option<std::result::Result<Vec<u8>, SplitError>> {
+    let mut parts: Vec<(u32, u32, &[u8])> = chunks
+        .into_iter()
+        .map(|chunk| parse_part(chunk.data()))
+        .collect::<Option<_>>()?;
+    if parts.is_empty() {
+        return None;
+    }
+    parts.sort_by_key(|&(index, _, _)| index);
+
+    let mut messages: Vec<u32> = parts
+        .iter()
+        .filter(|&&(index, _, _)| index == 0)
+        .map(|&(_, count, _)| count)
+        .collect();
+    for &(_, count, _) in &parts {
+        if !messages.contains(&count) {
+            messages.push(count);
+        }
+    }
+    if messages.len() > 1 {
+        messages.sort();
+        return Some(Err(SplitError::SeveralMessages(messages)));
+    }
+
+    let count = parts[0].1;
+    let mut payload = vec![];
+    for (expected, &(index, _, data)) in parts.iter().enumerate() {
+        match index.cmp(&(expected as u32)) {
+            std::cmp::Ordering::Less => return Some(Err(SplitError::DuplicatePart(index))),
+            std::cmp::Ordering::Greater => {
+                return Some(Err(SplitError::MissingPart(expected as u32)))
+            }
+            std::cmp::Ordering::Equal => payload.extend(data),
+        }
+    }
+    if parts.len() as u32 != count {
+        return Some(Err(SplitError::MissingPart(parts.len() as u32)));
+    }
+    Some(Ok(payload))
+}
+
+fn parse_part(data: &[u8]) -> Option<(u32, u32, &[u8])> {
+    let rest = data.strip_prefix(MAGIC)?;
+    let index = u32::from_be_bytes(rest.get(..4)?.try_into().unwrap());
+    let count = u32::from_be_bytes(rest.get(4..8)?.try_into().unwrap());
+    Some((index, count, &rest[8..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn parts(payload: &[u8], max_len: usize) -> Vec<Chunk> {
+        split(&ChunkType::from_str("ruSa").unwrap(), payload, max_len)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let payload: Vec<u8> = (0..100).collect();
+        let mut chunks = parts(&payload, HEADER_LEN + 30);
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks.iter().all(|c| c.data().len() <= HEADER_LEN + 30));
+
+        chunks.swap(0, 3);
+        assert_eq!(join(&chunks).unwrap().unwrap(), payload);
+    }
+
+    #[test]
+    fn test_missing_and_duplicate_parts() {
+        let mut chunks = parts(&[7; 100], HEADER_LEN + 30);
+        let removed = chunks.remove(1);
+        assert!(matches!(
+            join(&chunks),
+            Some(Err(SplitError::MissingPart(1)))
+        ));
+        chunks.push(removed.clone());
+        chunks.push(removed);
+        assert!(matches!(
+            join(&chunks),
+            Some(Err(SplitError::DuplicatePart(1)))
+        ));
+    }
+
+    #[test]
+    fn test_several_messages() {
+        let mut chunks = parts(&[1; 100], HEADER_LEN + 30);
+        chunks.extend(parts(&[2; 100], HEADER_LEN + 30));
+        let err = join(&chunks).unwrap().unwrap_err();
+        assert!(matches!(&err, SplitError::SeveralMessages(counts) if counts == &[4, 4]));
+
+        chunks.extend(parts(&[3; 10], HEADER_LEN + 30));
+        assert_eq!(
+            join(&chunks).unwrap().unwrap_err().to_string(),
+            "the chunks hold 3 split messages, of 1, 4 and 4 parts, and only one can be decoded"
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert!(matches!(escape(b"hello"), Cow::Borrowed(b"hello")));
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let escaped = Chunk::new(chunk_type, escape(b"SPLThello world").into_owned());
+        assert_eq!(join([&escaped]).unwrap().unwrap(), b"SPLThello world");
+    }
+
+    #[test]
+    fn test_not_parts() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"message".to_vec());
+        assert!(join([&chunk]).is_none());
+        assert!(join([]).is_none());
+    }
+}
@@ -0,0 +1,236 @@
+//! Payloads hidden in the pixels themselves rather than in a chunk. Each
+//! payload bit replaces the least significant bit of a color sample; alpha
+//! is left alone. The bits spell out a header followed by the payload:
+//!
+//! ```text
+//! "PSTG" | payload length (u32) | payload bytes
+//! ```
+//!
+//! Only 8- and 16-bit grayscale and truecolor images, with or without alpha,
+//! can carry a payload. The image data is re-encoded non-interlaced.
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::ihdr::Ihdr;
+use crate::pixels::{PixelError, Pixels};
+use crate::png::Png;
+
+const MAGIC: &[u8; 4] = b"PSTG";
+const HEADER_LEN: usize = MAGIC.len() + 4;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StegoError {
+    #[error(transparent)]
+    Pixels(#[from] PixelError),
+    #[error("cannot hide data in {bit_depth}-bit images of color type {color_type}")]
+    Unsupported { bit_depth: u8, color_type: u8 },
+    #[error("payload of {len} bytes exceeds the image's capacity of {capacity} bytes")]
+    TooLarge { len: usize, capacity: usize },
+    #[error("no hidden payload found")]
+    NotFound,
+}
+
+/// Number of payload bytes `png` can hide.
+pub fn capacity(png: &Png) -> std::result::Result<usize, StegoError> {
+    let ihdr = Ihdr::from_png(png).map_err(PixelError::from)?;
+    Ok(capacity_of(carriers(&ihdr)?.len()))
+}
+
+/// A copy of `png` with `payload` hidden in its pixels. Chunks other than
+/// IHDR and IDAT are kept as they are.
+pub fn embed(png: &Png, payload: &[u8]) -> std::result::Result<Png, StegoError> {
+    let mut pixels = Pixels::decode(png)?;
+    let carriers = carriers(&pixels.ihdr)?;
+    let capacity = capacity_of(carriers.len());
+    if payload.len() > capacity {
+        return Err(StegoError::TooLarge {
+            len: payload.len(),
+            capacity,
+        });
+    }
+
+    let mut message = Vec::with_capacity(HEADER_LEN + payload.len());
+    message.extend(MAGIC);
+    message.extend((payload.len() as u32).to_be_bytes());
+    message.extend(payload);
+    let bits = message
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |shift| (byte >> shift) & 1));
+    for (idx, bit) in carriers.zip(bits) {
+        pixels.data[idx] = (pixels.data[idx] & !1) | bit;
+    }
+
+    pixels.ihdr.interlace_method = 0;
+    Ok(replace_image_data(png, &pixels))
+}
+
+/// The payload hidden in `png` by [`embed`].
+pub fn extract(png: &Png) -> std::result::Result<Vec<u8>, StegoError> {
+    let pixels = Pixels::decode(png)?;
+    let carriers = carriers(&pixels.ihdr)?;
+    let capacity = capacity_of(carriers.len());
+    let mut bits = carriers.map(|idx| pixels.data[idx] & 1);
+    let mut bytes =
+        std::iter::from_fn(|| (0..8).try_fold(0, |byte, _| Some(byte << 1 | bits.next()?)));
+
+    let header: Vec<u8> = bytes.by_ref().take(HEADER_LEN).collect();
+    if header.len() < HEADER_LEN || !header.starts_with(MAGIC) {
+        return Err(StegoError::NotFound);
+    }
+    let len = u32::from_be_bytes(header[MAGIC.len()..].try_into().unwrap()) as usize;
+    if len > capacity {
+        return Err(StegoError::NotFound);
+    }
+    Ok(bytes.take(len).collect())
+}
+
+fn capacity_of(carriers: usize) -> usize {
+    (carriers / 8).saturating_sub(HEADER_LEN)
+}
+
+/// Indices into the decoded image data of the bytes whose lowest bit carries
+/// the payload: the least significant byte of every color sample.
+fn carriers(ihdr: &Ihdr) -> std::result::Result<impl ExactSizeIterator<Item = usize>, StegoError> {
+    if !matches!(ihdr.bit_depth, 8 | 16) || ihdr.color_type == 3 {
+        return Err(StegoError::Unsupported {
+            bit_depth: ihdr.bit_depth,
+            color_type: ihdr.color_type,
+        });
+    }
+    let sample_len = ihdr.bit_depth as usize / 8;
+    let channels = ihdr.channels();
+    let color_channels = match ihdr.color_type {
+        4 | 6 => channels - 1,
+        _ => channels,
+    };
+    // The decoded image is no larger than its filtered data, so once that
+    // fits in memory the products below cannot overflow.
+    ihdr.image_data_len().map_err(PixelError::from)?;
+    let len = ihdr.width as usize * ihdr.height as usize * color_channels;
+    Ok((0..len).map(move |sample| {
+        let pixel = sample / color_channels;
+        let channel = sample % color_channels;
+        (pixel * channels + channel) * sample_len + sample_len - 1
+    }))
+}
+
+/// `png` with its IHDR rewritten and its IDAT chunks replaced by a single one
+/// holding `pixels`, where the first IDAT was.
+fn replace_image_data(png: &Png, pixels: &Pixels) -> Png {
+    let is = |chunk: &Chunk, chunk_type: ChunkType| *chunk.chunk_type() == chunk_type;
+    let first_idat = png
+        .chunks()
+        .iter()
+        .position(|c| is(c, ChunkType::IDAT))
+        .unwrap();
+    let mut chunks: Vec<Chunk> = png
+        .chunks()
+        .iter()
+        .filter(|c| !is(c, ChunkType::IDAT))
+        .map(|c| match is(c, ChunkType::IHDR) {
+            true => pixels.ihdr.to_chunk(),
+            false => c.clone(),
+        })
+        .collect();
+    let idat = Chunk::new(ChunkType::IDAT, pixels.compress());
+    chunks.insert(first_idat, idat);
+    Png::from_chunks(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn testing_png(bit_depth: u8, color_type: u8) -> Png {
+        let ihdr = Ihdr {
+            width: 16,
+            height: 16,
+            bit_depth,
+            color_type,
+            compression_method: 0,
+            filter_method: 0,
+            interlace_method: 0,
+        };
+        let len = 16 * 16 * ihdr.bits_per_pixel() / 8;
+        let data = (0..len).map(|idx| (idx * 7 % 251) as u8).collect();
+        let mut png = Pixels { ihdr, data }.to_png();
+        let text = Chunk::new(
+            ChunkType::from_str("tEXt").unwrap(),
+            b"Title\0Dice".to_vec(),
+        );
+        png.insert_chunk_at(1, text);
+        png
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for (bit_depth, color_type) in [(8, 0), (8, 2), (8, 6), (16, 4), (16, 2)] {
+            let png = testing_png(bit_depth, color_type);
+            let stego = embed(&png, b"A secret message!").unwrap();
+            assert_eq!(extract(&stego).unwrap(), b"A secret message!");
+            assert_eq!(stego.chunks().len(), png.chunks().len());
+            assert_eq!(stego.chunks()[1].data(), b"Title\0Dice");
+        }
+    }
+
+    #[test]
+    fn test_pixels_barely_change() {
+        let png = testing_png(8, 6);
+        let stego = embed(&png, &[0xff; 64]).unwrap();
+        let before = Pixels::decode(&png).unwrap().data;
+        let after = Pixels::decode(&stego).unwrap().data;
+        for (idx, (a, b)) in before.iter().zip(&after).enumerate() {
+            assert!(a.abs_diff(*b) <= 1);
+            if idx % 4 == 3 {
+                assert_eq!(a, b, "alpha must not change");
+            }
+        }
+    }
+
+    #[test]
+    fn test_capacity() {
+        // 256 pixels of 3 color samples, one bit each
+        let png = testing_png(8, 6);
+        assert_eq!(capacity(&png).unwrap(), 256 * 3 / 8 - HEADER_LEN);
+        let payload = vec![7; capacity(&png).unwrap()];
+        assert!(embed(&png, &payload).is_ok());
+        assert!(matches!(
+            embed(&png, &[payload, vec![7]].concat()),
+            Err(StegoError::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_carriers() {
+        let ihdr = Ihdr::from_png(&testing_png(16, 4)).unwrap();
+        // low byte of the gray sample, skipping the two alpha bytes
+        assert_eq!(
+            carriers(&ihdr).unwrap().take(3).collect::<Vec<_>>(),
+            [1, 5, 9]
+        );
+
+        let huge = Ihdr {
+            width: (1 << 31) - 1,
+            height: (1 << 31) - 1,
+            color_type: 6,
+            ..ihdr
+        };
+        assert!(matches!(
+            carriers(&huge),
+            Err(StegoError::Pixels(PixelError::Ihdr(_)))
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_and_not_found() {
+        assert!(matches!(
+            embed(&testing_png(4, 0), b"hi"),
+            Err(StegoError::Unsupported { .. })
+        ));
+        assert!(matches!(
+            extract(&testing_png(8, 2)),
+            Err(StegoError::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,199 @@
+//! Reading and writing PNG bytes from local paths or, with the `s3` feature,
+//! from `s3://bucket/key` URLs. S3 credentials, region and custom endpoints
+//! are taken from the usual `AWS_*` environment variables. Paths ending in
+//! `.gz` are transparently decompressed on read and compressed on write. The
+//! path `-` reads stdin.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use pngme::Result;
+
+/// Bucket and key of an `s3://bucket/key` path.
+fn s3_location(path: &Path) -> Option<(&str, &str)> {
+    path.to_str()?.strip_prefix("s3://")?.split_once('/')
+}
+
+pub fn is_remote(path: &Path) -> bool {
+    s3_location(path).is_some()
+}
+
+fn is_stdin(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+pub fn is_gzip(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+pub fn gzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(bytes)?;
+    Ok(encoder.finish()?)
+}
+
+pub fn gunzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut decompressed = vec![];
+    GzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+pub fn read(path: &Path) -> Result<Vec<u8>> {
+    if is_stdin(path) {
+        return read_stdin();
+    }
+    let bytes = match s3_location(path) {
+        Some((bucket, key)) => s3::read(bucket, key)?,
+        None => std::fs::read(path)?,
+    };
+    if is_gzip(path) {
+        return gunzip(&bytes);
+    }
+    Ok(bytes)
+}
+
+/// All of stdin, decompressed if it is gzip-compressed.
+fn read_stdin() -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    std::io::stdin().read_to_end(&mut bytes)?;
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        return gunzip(&bytes);
+    }
+    Ok(bytes)
+}
+
+/// A buffered reader over the file at `path`. Local files are streamed and
+/// decompressed on the fly; S3 objects and stdin are read whole.
+pub fn open(path: &Path) -> Result<Box<dyn BufRead>> {
+    if is_remote(path) || is_stdin(path) {
+        return Ok(Box::new(Cursor::new(read(path)?)));
+    }
+    let file = File::open(path)?;
+    if is_gzip(path) {
+        return Ok(Box::new(BufReader::new(GzDecoder::new(file))));
+    }
+    Ok(Box::new(BufReader::new(file)))
+}
+
+pub fn write(path: &Path, bytes: &[u8]) -> Result<()> {
+    write_with(path, |writer| writer.write_all(bytes))
+}
+
+/// Writes the output of `f` to `path`. Local files are written through a
+/// buffer as `f` produces them, to a temporary file next to `path` that then
+/// replaces it, so a failure midway leaves any existing file intact. S3
+/// objects are uploaded once `f` is done.
+pub fn write_with(
+    path: &Path,
+    f: impl FnOnce(&mut dyn Write) -> std::io::Result<()>,
+) -> Result<()> {
+    if let Some((bucket, key)) = s3_location(path) {
+        let mut bytes = vec![];
+        f(&mut bytes)?;
+        if is_gzip(path) {
+            bytes = gzip(&bytes)?;
+        }
+        return s3::write(bucket, key, &bytes);
+    }
+
+    let tmp = temp_path(path);
+    let result = write_file(&tmp, is_gzip(path), f).and_then(|()| {
+        if let Ok(metadata) = std::fs::metadata(path) {
+            std::fs::set_permissions(&tmp, metadata.permissions())?;
+        }
+        std::fs::rename(&tmp, path)
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    Ok(result?)
+}
+
+/// Copies the local file at `path` to `path.bak`, replacing any older backup.
+pub fn backup(path: &Path) -> Result<()> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    std::fs::copy(path, backup)?;
+    Ok(())
+}
+
+/// A hidden file in the same directory as `path`, so renaming it over
+/// `path` does not cross file systems.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+fn write_file(
+    path: &Path,
+    gzip: bool,
+    f: impl FnOnce(&mut dyn Write) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    if gzip {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        f(&mut encoder)?;
+        writer = encoder.finish()?;
+    } else {
+        f(&mut writer)?;
+    }
+    writer.into_inner()?.sync_all()
+}
+
+#[cfg(feature = "s3")]
+mod s3 {
+    use object_store::{aws::AmazonS3Builder, path::Path, ObjectStore, ObjectStoreExt, PutPayload};
+
+    use pngme::Result;
+
+    fn run<T>(
+        bucket: &str,
+        f: impl AsyncFnOnce(&dyn ObjectStore) -> object_store::Result<T>,
+    ) -> Result<T> {
+        let store = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .build()?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Ok(runtime.block_on(f(&store))?)
+    }
+
+    pub fn read(bucket: &str, key: &str) -> Result<Vec<u8>> {
+        run(bucket, async |store| {
+            let object = store.get(&Path::from(key)).await?;
+            Ok(object.bytes().await?.to_vec())
+        })
+    }
+
+    pub fn write(bucket: &str, key: &str, bytes: &[u8]) -> Result<()> {
+        run(bucket, async |store| {
+            store
+                .put(&Path::from(key), PutPayload::from(bytes.to_vec()))
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(not(feature = "s3"))]
+mod s3 {
+    use pngme::Result;
+
+    const UNSUPPORTED: &str = "s3:// paths require pngme to be built with the `s3` feature";
+
+    pub fn read(_bucket: &str, _key: &str) -> Result<Vec<u8>> {
+        Err(UNSUPPORTED.into())
+    }
+
+    pub fn write(_bucket: &str, _key: &str, _bytes: &[u8]) -> Result<()> {
+        Err(UNSUPPORTED.into())
+    }
+}
@@ -0,0 +1,237 @@
+//! Messages addressed by key instead of by chunk type. All entries live in a
+//! single private chunk of type [`MessageStore::CHUNK_TYPE`]:
+//!
+//! ```text
+//! "PMKV" | entries, each: key length (u16) | key (UTF-8) | value length (u32) | value
+//! ```
+
+use std::str::FromStr;
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+
+const MAGIC: &[u8; 4] = b"PMKV";
+
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("message store does not start with its marker")]
+    BadMarker,
+    #[error("message store entry {0} is truncated")]
+    Truncated(usize),
+    #[error("message store entry {0} has a key that is not UTF-8")]
+    KeyNotUtf8(usize),
+    #[error("keys must be 1 to 65535 bytes long")]
+    BadKeyLength,
+    #[error("message store of {0} bytes does not fit in a chunk")]
+    TooLarge(usize),
+}
+
+/// Key-value pairs stored in a PNG, in insertion order.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MessageStore {
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl MessageStore {
+    /// Private, ancillary and safe to copy, so editors keep it.
+    pub const CHUNK_TYPE: &'static str = "kvSt";
+
+    /// The store in `png`, empty if it has none.
+    pub fn load(png: &Png) -> std::result::Result<MessageStore, StoreError> {
+        match png.chunk_by_type(Self::CHUNK_TYPE) {
+            Some(chunk) => MessageStore::parse(chunk.data()),
+            None => Ok(MessageStore::default()),
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> std::result::Result<MessageStore, StoreError> {
+        let mut rest = data.strip_prefix(MAGIC).ok_or(StoreError::BadMarker)?;
+        let mut entries = vec![];
+        while !rest.is_empty() {
+            let idx = entries.len();
+            let (key_len, tail) = rest
+                .split_first_chunk::<2>()
+                .ok_or(StoreError::Truncated(idx))?;
+            let key_len = u16::from_be_bytes(*key_len) as usize;
+            let key = tail.get(..key_len).ok_or(StoreError::Truncated(idx))?;
+            let key = String::from_utf8(key.to_vec()).map_err(|_| StoreError::KeyNotUtf8(idx))?;
+            let (value_len, tail) = tail[key_len..]
+                .split_first_chunk::<4>()
+                .ok_or(StoreError::Truncated(idx))?;
+            let value_len = u32::from_be_bytes(*value_len) as usize;
+            let value = tail.get(..value_len).ok_or(StoreError::Truncated(idx))?;
+            entries.push((key, value.to_vec()));
+            rest = &tail[value_len..];
+        }
+        Ok(MessageStore { entries })
+    }
+
+    /// Writes the store into `png`, replacing its previous store chunk or
+    /// adding one before IEND. An empty store removes the chunk. Fails, and
+    /// leaves `png` alone, if the store no longer fits in a chunk.
+    pub fn save(&self, png: &mut Png) -> std::result::Result<(), StoreError> {
+        let len = self.byte_len();
+        if len > Chunk::MAX_LENGTH {
+            return Err(StoreError::TooLarge(len));
+        }
+
+        let is_store = |c: &Chunk| c.chunk_type().to_string() == Self::CHUNK_TYPE;
+        let position = png.chunks().iter().position(is_store);
+        png.remove_chunks_where(is_store);
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+
+        let chunk = Chunk::new(
+            ChunkType::from_str(Self::CHUNK_TYPE).unwrap(),
+            self.as_bytes(),
+        );
+        match position {
+            Some(index) => png.insert_chunk_at(index, chunk),
+            None => png.insert_before_iend([chunk]),
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| &value[..])
+    }
+
+    /// Sets `key` to `value`, keeping its place if it already exists.
+    pub fn set(&mut self, key: &str, value: Vec<u8>) -> std::result::Result<(), StoreError> {
+        if key.is_empty() || key.len() > u16::MAX as usize {
+            return Err(StoreError::BadKeyLength);
+        }
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, old)) => *old = value,
+            None => self.entries.push((key.to_string(), value)),
+        }
+        Ok(())
+    }
+
+    /// Removes `key`, returning its value if it was set.
+    pub fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+        let idx = self.entries.iter().position(|(k, _)| k == key)?;
+        Some(self.entries.remove(idx).1)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(k, _)| k.as_str())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), &v[..]))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Length of [`MessageStore::as_bytes`], without building it.
+    pub fn byte_len(&self) -> usize {
+        let entries: usize = self
+            .entries
+            .iter()
+            .map(|(key, value)| 2 + key.len() + 4 + value.len())
+            .sum();
+        MAGIC.len() + entries
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        for (key, value) in &self.entries {
+            bytes.extend((key.len() as u16).to_be_bytes());
+            bytes.extend(key.as_bytes());
+            bytes.extend((value.len() as u32).to_be_bytes());
+            bytes.extend(value);
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testing_png() -> Png {
+        let chunk = |t: &str| Chunk::new(ChunkType::from_str(t).unwrap(), vec![]);
+        Png::from_chunks(vec![chunk("IHDR"), chunk("IDAT"), chunk("IEND")])
+    }
+
+    fn types(png: &Png) -> Vec<String> {
+        png.chunks()
+            .iter()
+            .map(|c| c.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut store = MessageStore::default();
+        store.set("build-info", b"v1.2".to_vec()).unwrap();
+        store.set("author", b"Jane".to_vec()).unwrap();
+        store.set("build-info", b"v1.3".to_vec()).unwrap();
+        assert_eq!(store.byte_len(), store.as_bytes().len());
+        let parsed = MessageStore::parse(&store.as_bytes()).unwrap();
+        assert_eq!(parsed, store);
+        assert_eq!(parsed.keys().collect::<Vec<_>>(), ["build-info", "author"]);
+        assert_eq!(parsed.get("build-info"), Some(&b"v1.3"[..]));
+        assert_eq!(parsed.get("missing"), None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut png = testing_png();
+        assert!(MessageStore::load(&png).unwrap().is_empty());
+
+        let mut store = MessageStore::default();
+        store.set("build-info", b"v1.2".to_vec()).unwrap();
+        store.save(&mut png).unwrap();
+        assert_eq!(types(&png), ["IHDR", "IDAT", "kvSt", "IEND"]);
+
+        let mut store = MessageStore::load(&png).unwrap();
+        store.set("author", b"Jane".to_vec()).unwrap();
+        store.save(&mut png).unwrap();
+        assert_eq!(types(&png), ["IHDR", "IDAT", "kvSt", "IEND"]);
+        assert_eq!(MessageStore::load(&png).unwrap().len(), 2);
+
+        let mut store = MessageStore::load(&png).unwrap();
+        assert_eq!(store.remove("author"), Some(b"Jane".to_vec()));
+        assert_eq!(store.remove("author"), None);
+        store.remove("build-info");
+        store.save(&mut png).unwrap();
+        assert_eq!(types(&png), ["IHDR", "IDAT", "IEND"]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            MessageStore::parse(b"ruSt"),
+            Err(StoreError::BadMarker)
+        ));
+        let mut store = MessageStore::default();
+        store.set("key", b"value".to_vec()).unwrap();
+        let bytes = store.as_bytes();
+        assert!(matches!(
+            MessageStore::parse(&bytes[..bytes.len() - 1]),
+            Err(StoreError::Truncated(0))
+        ));
+        assert!(matches!(
+            store.set("", vec![]),
+            Err(StoreError::BadKeyLength)
+        ));
+
+        let mut png = testing_png();
+        store.set("huge", vec![0; Chunk::MAX_LENGTH]).unwrap();
+        assert!(matches!(store.save(&mut png), Err(StoreError::TooLarge(_))));
+        assert_eq!(types(&png), ["IHDR", "IDAT", "IEND"]);
+    }
+}
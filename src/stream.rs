@@ -0,0 +1,196 @@
+//! Chunk-by-chunk reading, so large files can be processed without holding
+//! them in memory whole. Chunk data is only read when asked for.
+
+use std::io::{BufRead, Read, Write};
+
+use crate::chunk::{Chunk, ChunkError};
+use crate::chunk_type::ChunkType;
+use crate::png::{Png, PngError};
+use crate::Result;
+
+/// Length and type of a chunk, read ahead of its data.
+#[derive(Debug)]
+pub struct ChunkHeader {
+    /// Offset of the length field from the start of the file.
+    pub offset: u64,
+    pub length: u32,
+    pub chunk_type: ChunkType,
+}
+
+pub struct ChunkReader<R> {
+    reader: R,
+    offset: u64,
+    /// Type and length of the chunk whose data has not been consumed yet.
+    pending: Option<(ChunkType, u32)>,
+}
+
+impl<R: BufRead> ChunkReader<R> {
+    /// Reads and checks the PNG signature.
+    pub fn new(mut reader: R) -> Result<ChunkReader<R>> {
+        let mut header = [0; 8];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| PngError::WrongHeader)?;
+        if header != Png::STANDARD_HEADER {
+            return Err(PngError::WrongHeader.into());
+        }
+        Ok(ChunkReader {
+            reader,
+            offset: header.len() as u64,
+            pending: None,
+        })
+    }
+
+    /// The header of the next chunk, skipping the data of the current one if
+    /// it was not read. `None` at the end of the input.
+    pub fn next_header(&mut self) -> Result<Option<ChunkHeader>> {
+        if self.pending.is_some() {
+            self.skip_data()?;
+        }
+        if self.reader.fill_buf()?.is_empty() {
+            return Ok(None);
+        }
+
+        let mut header = [0; 8];
+        self.reader
+            .read_exact(&mut header)
+            .map_err(|_| PngError::Corrupted)?;
+        let length = u32::from_be_bytes(header[..4].try_into().unwrap());
+        if length as usize > Chunk::MAX_LENGTH {
+            return Err(ChunkError::TooLong(length).into());
+        }
+        let type_bytes: [u8; 4] = header[4..].try_into().unwrap();
+        let chunk_type = ChunkType::try_from(type_bytes).map_err(|_| PngError::Corrupted)?;
+
+        let header = ChunkHeader {
+            offset: self.offset,
+            length,
+            chunk_type,
+        };
+        self.offset += 8;
+        self.pending = Some((chunk_type, length));
+        Ok(Some(header))
+    }
+
+    /// Streams the data of the current chunk to `writer` and checks its CRC,
+    /// using a fixed amount of memory whatever the chunk size.
+    pub fn copy_data(&mut self, writer: &mut impl Write) -> Result<()> {
+        let (chunk_type, length) = self.pending.take().ok_or(PngError::ChunkNotFound)?;
+        let mut crc = crc::crc32::update(0, &crc::crc32::IEEE_TABLE, &chunk_type.bytes());
+        let mut remaining = length as usize;
+        while remaining > 0 {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                return Err(PngError::Corrupted.into());
+            }
+            let n = buf.len().min(remaining);
+            crc = crc::crc32::update(crc, &crc::crc32::IEEE_TABLE, &buf[..n]);
+            writer.write_all(&buf[..n])?;
+            self.reader.consume(n);
+            remaining -= n;
+        }
+
+        let stored = self.read_crc()?;
+        self.offset += length as u64 + 4;
+        if stored != crc {
+            return Err(ChunkError::CrcMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Reads the data of the current chunk and checks its CRC.
+    pub fn read_data(&mut self) -> Result<Vec<u8>> {
+        // The length is not trusted with more than a modest allocation up
+        // front; a truncated file claiming a huge chunk fails once read.
+        let length = self.pending.as_ref().map_or(0, |(_, length)| *length);
+        let mut data = Vec::with_capacity((length as usize).min(1 << 20));
+        self.copy_data(&mut data)?;
+        Ok(data)
+    }
+
+    /// Discards the data and CRC of the current chunk without checking them.
+    pub fn skip_data(&mut self) -> Result<()> {
+        let (_, length) = self.pending.take().ok_or(PngError::ChunkNotFound)?;
+        let skip = length as u64 + 4;
+        let skipped = std::io::copy(&mut (&mut self.reader).take(skip), &mut std::io::sink())?;
+        if skipped != skip {
+            return Err(PngError::Corrupted.into());
+        }
+        self.offset += skip;
+        Ok(())
+    }
+
+    /// The next whole chunk, `None` at the end of the input.
+    pub fn next_chunk(&mut self) -> Result<Option<Chunk>> {
+        match self.next_header()? {
+            Some(header) => Ok(Some(Chunk::new(header.chunk_type, self.read_data()?))),
+            None => Ok(None),
+        }
+    }
+
+    fn read_crc(&mut self) -> Result<u32> {
+        let mut crc = [0; 4];
+        self.reader
+            .read_exact(&mut crc)
+            .map_err(|_| PngError::Corrupted)?;
+        Ok(u32::from_be_bytes(crc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn testing_png() -> Png {
+        let chunk = |chunk_type: &str, data: &[u8]| {
+            Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+        };
+        Png::from_chunks(vec![
+            chunk("FrSt", b"first"),
+            chunk("ruSt", &[7; 100_000]),
+            chunk("LASt", b"last"),
+        ])
+    }
+
+    #[test]
+    fn test_skip_and_read() {
+        let bytes = testing_png().as_bytes();
+        let mut reader = ChunkReader::new(&bytes[..]).unwrap();
+
+        let first = reader.next_header().unwrap().unwrap();
+        assert_eq!((first.offset, first.length), (8, 5));
+        let large = reader.next_header().unwrap().unwrap();
+        assert_eq!(large.chunk_type.to_string(), "ruSt");
+        assert_eq!(large.offset, 8 + 12 + 5);
+
+        let last = reader.next_header().unwrap().unwrap();
+        assert_eq!(last.chunk_type.to_string(), "LASt");
+        assert_eq!(reader.read_data().unwrap(), b"last");
+        assert!(reader.next_header().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_crc_mismatch() {
+        let mut bytes = testing_png().as_bytes();
+        // first data byte of FrSt
+        bytes[8 + 8] ^= 0xff;
+        let mut reader = ChunkReader::new(&bytes[..]).unwrap();
+        reader.next_header().unwrap();
+        assert!(reader.read_data().is_err());
+        // the reader moves on to the next chunk
+        let next = reader.next_header().unwrap().unwrap();
+        assert_eq!(next.chunk_type.to_string(), "ruSt");
+    }
+
+    #[test]
+    fn test_truncated() {
+        let bytes = testing_png().as_bytes();
+        let mut reader = ChunkReader::new(&bytes[..bytes.len() - 2]).unwrap();
+        let mut result = Ok(Some(()));
+        while let Ok(Some(())) = result {
+            result = reader.next_chunk().map(|c| c.map(|_| ()));
+        }
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,90 @@
+//! `{placeholder}` interpolation in user-supplied values. `{{` and `}}`
+//! stand for literal braces.
+
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    #[error("unterminated placeholder starting at byte {0}")]
+    Unterminated(usize),
+    #[error("unmatched '}}' at byte {0}")]
+    UnmatchedBrace(usize),
+    #[error("cannot expand {{{0}}}: {1}")]
+    Unresolved(String, String),
+}
+
+/// Replaces every `{name}` in `template` with `resolve(name)`.
+pub fn interpolate(
+    template: &str,
+    mut resolve: impl FnMut(&str) -> std::result::Result<String, String>,
+) -> std::result::Result<String, TemplateError> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(pos) = rest.find(['{', '}']) {
+        let offset = template.len() - rest.len() + pos;
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            out.push_str(&tail[..1]);
+            rest = &tail[2..];
+        } else if tail.starts_with('}') {
+            return Err(TemplateError::UnmatchedBrace(offset));
+        } else {
+            let end = tail.find('}').ok_or(TemplateError::Unterminated(offset))?;
+            let name = &tail[1..end];
+            let value =
+                resolve(name).map_err(|e| TemplateError::Unresolved(name.to_string(), e))?;
+            out.push_str(&value);
+            rest = &tail[end + 1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(name: &str) -> std::result::Result<String, String> {
+        match name {
+            "who" => Ok("world".to_string()),
+            _ => Err("unknown placeholder".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(
+            interpolate("hello {who}!", resolve).unwrap(),
+            "hello world!"
+        );
+        assert_eq!(
+            interpolate("no placeholders", resolve).unwrap(),
+            "no placeholders"
+        );
+    }
+
+    #[test]
+    fn test_escaped_braces() {
+        assert_eq!(
+            interpolate("{{who}} {who}", resolve).unwrap(),
+            "{who} world"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(
+            interpolate("a {who", resolve),
+            Err(TemplateError::Unterminated(2))
+        ));
+        assert!(matches!(
+            interpolate("a } b", resolve),
+            Err(TemplateError::UnmatchedBrace(2))
+        ));
+        assert!(matches!(
+            interpolate("{nope}", resolve),
+            Err(TemplateError::Unresolved(_, _))
+        ));
+    }
+}
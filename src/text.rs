@@ -0,0 +1,258 @@
+//! Textual metadata chunks: tEXt, zTXt and iTXt.
+
+use crate::chunk::{Chunk, ChunkError};
+use crate::chunk_type::ChunkType;
+use crate::zlib::{self, ZlibError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TextError {
+    #[error("text chunk has no keyword separator")]
+    MissingSeparator,
+    #[error("text is not valid UTF-8")]
+    InvalidUtf8,
+    #[error("keyword must be 1 to 79 printable Latin-1 characters")]
+    InvalidKeyword,
+    #[error("text cannot be stored as Latin-1, use iTXt")]
+    NotLatin1,
+    #[error(transparent)]
+    Zlib(#[from] ZlibError),
+    #[error(transparent)]
+    Chunk(#[from] ChunkError),
+}
+
+/// Largest text a zTXt or iTXt chunk may inflate to, so that a small chunk
+/// cannot expand into gigabytes.
+pub const MAX_TEXT_LEN: usize = 16 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum TextKind {
+    /// tEXt: uncompressed Latin-1
+    #[serde(rename = "tEXt")]
+    Text,
+    /// zTXt: compressed Latin-1
+    #[serde(rename = "zTXt")]
+    Compressed,
+    /// iTXt: UTF-8, optionally compressed
+    #[serde(rename = "iTXt")]
+    International,
+}
+
+/// A decoded keyword/value pair.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct TextChunk {
+    pub kind: TextKind,
+    pub keyword: String,
+    pub text: String,
+    /// Language tag and translated keyword of iTXt chunks, empty otherwise.
+    pub language: String,
+    pub translated_keyword: String,
+}
+
+impl TextChunk {
+    /// Decodes `chunk`, or returns `None` if it is not a text chunk.
+    pub fn parse(chunk: &Chunk) -> Option<std::result::Result<TextChunk, TextError>> {
+        let kind = match *chunk.chunk_type() {
+            ChunkType::TEXT => TextKind::Text,
+            ChunkType::ZTXT => TextKind::Compressed,
+            ChunkType::ITXT => TextKind::International,
+            _ => return None,
+        };
+        Some(Self::parse_data(kind, chunk))
+    }
+
+    /// An uncompressed tEXt, a zTXt or an uncompressed iTXt chunk.
+    pub fn new(kind: TextKind, keyword: &str, text: &str) -> TextChunk {
+        TextChunk {
+            kind,
+            keyword: keyword.to_string(),
+            text: text.to_string(),
+            language: String::new(),
+            translated_keyword: String::new(),
+        }
+    }
+
+    /// Encodes the chunk, checking the keyword and, for tEXt and zTXt, that
+    /// the text fits Latin-1.
+    pub fn to_chunk(&self) -> std::result::Result<Chunk, TextError> {
+        let printable = |c: char| matches!(c as u32, 32..=126 | 161..=255);
+        if self.keyword.is_empty()
+            || self.keyword.chars().count() > 79
+            || !self.keyword.chars().all(printable)
+        {
+            return Err(TextError::InvalidKeyword);
+        }
+
+        let mut data = to_latin1(&self.keyword).unwrap();
+        data.push(0);
+        let chunk_type = match self.kind {
+            TextKind::Text => {
+                data.extend(to_latin1(&self.text).ok_or(TextError::NotLatin1)?);
+                ChunkType::TEXT
+            }
+            TextKind::Compressed => {
+                // compression method 0 (deflate)
+                data.push(0);
+                data.extend(zlib::deflate(
+                    &to_latin1(&self.text).ok_or(TextError::NotLatin1)?,
+                ));
+                ChunkType::ZTXT
+            }
+            TextKind::International => {
+                // uncompressed, compression method 0
+                data.extend([0, 0]);
+                data.extend(self.language.as_bytes());
+                data.push(0);
+                data.extend(self.translated_keyword.as_bytes());
+                data.push(0);
+                data.extend(self.text.as_bytes());
+                ChunkType::ITXT
+            }
+        };
+        Ok(Chunk::try_new(chunk_type, data)?)
+    }
+
+    fn parse_data(kind: TextKind, chunk: &Chunk) -> std::result::Result<TextChunk, TextError> {
+        let data = chunk.data();
+        let (keyword, rest) = split_nul(data).ok_or(TextError::MissingSeparator)?;
+        let mut text = TextChunk {
+            kind,
+            keyword: latin1(keyword),
+            text: String::new(),
+            language: String::new(),
+            translated_keyword: String::new(),
+        };
+
+        match kind {
+            TextKind::Text => text.text = latin1(rest),
+            TextKind::Compressed => {
+                let stream = zlib::chunk_stream(chunk).unwrap()?;
+                text.text = latin1(&zlib::inflate_limited(stream, MAX_TEXT_LEN)?);
+            }
+            TextKind::International => {
+                let compressed = rest.first() == Some(&1);
+                let (language, rest) = split_nul(rest.get(2..).unwrap_or_default())
+                    .ok_or(TextError::MissingSeparator)?;
+                let (translated, value) = split_nul(rest).ok_or(TextError::MissingSeparator)?;
+                let value = if compressed {
+                    zlib::inflate_limited(zlib::chunk_stream(chunk).unwrap()?, MAX_TEXT_LEN)?
+                } else {
+                    value.to_vec()
+                };
+                text.language = latin1(language);
+                text.translated_keyword = utf8(translated)?;
+                text.text = utf8(&value)?;
+            }
+        }
+        Ok(text)
+    }
+}
+
+impl std::fmt::Display for TextChunk {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.keyword)?;
+        match (self.language.is_empty(), self.translated_keyword.is_empty()) {
+            (true, true) => {}
+            (_, true) => write!(f, " [{}]", self.language)?,
+            _ => write!(f, " [{}: {}]", self.language, self.translated_keyword)?,
+        }
+        write!(f, ": {}", self.text)
+    }
+}
+
+fn split_nul(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let nul = bytes.iter().position(|&b| b == 0)?;
+    Some((&bytes[..nul], &bytes[nul + 1..]))
+}
+
+fn to_latin1(s: &str) -> Option<Vec<u8>> {
+    s.chars().map(|c| u8::try_from(c as u32).ok()).collect()
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).collect()
+}
+
+fn utf8(bytes: &[u8]) -> std::result::Result<String, TextError> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| TextError::InvalidUtf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    #[test]
+    fn test_parse_text() {
+        let text = TextChunk::parse(&chunk("tEXt", b"Author\0J\xf6rg"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(text.keyword, "Author");
+        assert_eq!(text.text, "Jörg");
+    }
+
+    #[test]
+    fn test_parse_compressed() {
+        let mut data = b"Comment\0\0".to_vec();
+        data.extend(zlib::deflate(b"squeezed"));
+        let text = TextChunk::parse(&chunk("zTXt", &data)).unwrap().unwrap();
+        assert_eq!(text.kind, TextKind::Compressed);
+        assert_eq!(text.text, "squeezed");
+    }
+
+    #[test]
+    fn test_parse_compressed_too_large() {
+        let mut data = b"Comment\0\0".to_vec();
+        data.extend(zlib::deflate(&vec![b'a'; MAX_TEXT_LEN + 1]));
+        let parsed = TextChunk::parse(&chunk("zTXt", &data)).unwrap();
+        assert!(matches!(
+            parsed,
+            Err(TextError::Zlib(ZlibError::TooLarge(MAX_TEXT_LEN)))
+        ));
+    }
+
+    #[test]
+    fn test_parse_international() {
+        let text = TextChunk::parse(&chunk("iTXt", "Title\0\0\0de\0Titel\0Grüße".as_bytes()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(text.language, "de");
+        assert_eq!(text.translated_keyword, "Titel");
+        assert_eq!(text.text, "Grüße");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut international = TextChunk::new(TextKind::International, "Title", "Grüße 👋");
+        international.language = "de".to_string();
+        international.translated_keyword = "Titel".to_string();
+        for text in [
+            TextChunk::new(TextKind::Text, "Author", "Jörg"),
+            TextChunk::new(TextKind::Compressed, "Comment", "squeezed"),
+            international,
+        ] {
+            let chunk = text.to_chunk().unwrap();
+            assert_eq!(TextChunk::parse(&chunk).unwrap().unwrap(), text);
+        }
+    }
+
+    #[test]
+    fn test_to_chunk_invalid() {
+        let text = TextChunk::new(TextKind::Text, "Comment", "👋");
+        assert!(matches!(text.to_chunk(), Err(TextError::NotLatin1)));
+        let text = TextChunk::new(TextKind::International, &"k".repeat(80), "");
+        assert!(matches!(text.to_chunk(), Err(TextError::InvalidKeyword)));
+    }
+
+    #[test]
+    fn test_parse_other_chunk() {
+        assert!(TextChunk::parse(&chunk("ruSt", b"a\0b")).is_none());
+        assert!(TextChunk::parse(&chunk("tEXt", b"no separator"))
+            .unwrap()
+            .is_err());
+    }
+}
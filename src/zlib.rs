@@ -0,0 +1,242 @@
+//! Strict zlib inflation and lookup of the zlib streams carried by chunks.
+
+use std::io::Write;
+
+use flate2::{write::ZlibEncoder, Compression, Decompress, FlushDecompress, Status};
+
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::ihdr::Ihdr;
+use crate::png::Png;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ZlibError {
+    #[error("corrupt zlib stream: {0}")]
+    Corrupt(String),
+    #[error("zlib stream is truncated")]
+    Truncated,
+    #[error("{0} bytes of trailing data after zlib stream")]
+    TrailingData(usize),
+    #[error("unsupported compression method {0}")]
+    UnsupportedMethod(u8),
+    #[error("malformed {0} chunk")]
+    Malformed(String),
+    #[error("zlib stream inflates to more than {0} bytes")]
+    TooLarge(usize),
+}
+
+/// Inflates a complete zlib stream. Unlike a plain decoder this fails when the
+/// Adler-32 checksum does not match, the stream ends early, or bytes follow it.
+pub fn inflate(data: &[u8]) -> std::result::Result<Vec<u8>, ZlibError> {
+    inflate_limited(data, usize::MAX)
+}
+
+/// Like [`inflate`], failing as soon as the output exceeds `limit` bytes.
+pub fn inflate_limited(data: &[u8], limit: usize) -> std::result::Result<Vec<u8>, ZlibError> {
+    let mut decompress = Decompress::new(true);
+    let initial = data.len().saturating_mul(2).max(64);
+    let mut out = Vec::with_capacity(initial.min(limit.saturating_add(1)));
+    loop {
+        if out.len() > limit {
+            return Err(ZlibError::TooLarge(limit));
+        }
+        if out.len() == out.capacity() {
+            let room = limit.saturating_add(1) - out.len();
+            out.reserve(out.capacity().clamp(1, room));
+        }
+        let consumed = decompress.total_in() as usize;
+        let status = decompress
+            .decompress_vec(&data[consumed..], &mut out, FlushDecompress::None)
+            .map_err(|e| ZlibError::Corrupt(e.to_string()))?;
+
+        if status == Status::StreamEnd {
+            if out.len() > limit {
+                return Err(ZlibError::TooLarge(limit));
+            }
+            break;
+        }
+        if decompress.total_in() as usize == data.len() && out.len() < out.capacity() {
+            return Err(ZlibError::Truncated);
+        }
+    }
+
+    let trailing = data.len() - decompress.total_in() as usize;
+    if trailing > 0 {
+        return Err(ZlibError::TrailingData(trailing));
+    }
+    Ok(out)
+}
+
+/// Compresses `data` into a zlib stream at the best compression level.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(vec![], Compression::best());
+    // Writing to a Vec cannot fail.
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Returns the zlib stream embedded in a zTXt, iTXt or iCCP chunk, `None` for
+/// chunks that carry no compressed data.
+pub fn chunk_stream(chunk: &Chunk) -> Option<std::result::Result<&[u8], ZlibError>> {
+    let data = chunk.data();
+    let malformed = || ZlibError::Malformed(chunk.chunk_type().to_string());
+
+    let stream = match *chunk.chunk_type() {
+        // keyword, NUL, compression method, stream
+        ChunkType::ZTXT | ChunkType::ICCP => after_nul(data)
+            .filter(|&p| p < data.len())
+            .ok_or_else(malformed)
+            .and_then(|p| match data[p] {
+                0 => Ok(&data[p + 1..]),
+                m => Err(ZlibError::UnsupportedMethod(m)),
+            }),
+        // keyword, NUL, compression flag, compression method, language tag,
+        // NUL, translated keyword, NUL, text
+        ChunkType::ITXT => {
+            let p = after_nul(data).filter(|&p| p + 2 <= data.len());
+            let Some(p) = p else {
+                return Some(Err(malformed()));
+            };
+            if data[p] == 0 {
+                return None;
+            }
+            if data[p + 1] != 0 {
+                return Some(Err(ZlibError::UnsupportedMethod(data[p + 1])));
+            }
+            let rest = &data[p + 2..];
+            after_nul(rest)
+                .and_then(|lang| after_nul(&rest[lang..]).map(|t| lang + t))
+                .map(|text| &rest[text..])
+                .ok_or_else(malformed)
+        }
+        _ => return None,
+    };
+    Some(stream)
+}
+
+fn after_nul(bytes: &[u8]) -> Option<usize> {
+    bytes.iter().position(|&b| b == 0).map(|p| p + 1)
+}
+
+/// Outcome of inflating one zlib stream of a PNG.
+pub struct StreamReport {
+    /// "IDAT" for the image data, otherwise the chunk type and its index.
+    pub label: String,
+    pub result: std::result::Result<usize, ZlibError>,
+}
+
+/// Largest stream of a zTXt, iTXt or iCCP chunk that [`verify_streams`]
+/// inflates.
+pub const MAX_CHUNK_STREAM_LEN: usize = 16 << 20;
+
+/// Inflates the image data and every compressed ancillary chunk. The image
+/// data may inflate to no more than IHDR implies, or to
+/// [`MAX_CHUNK_STREAM_LEN`] without a usable IHDR, and the other streams to
+/// [`MAX_CHUNK_STREAM_LEN`]; a stream over its limit is reported as failing.
+pub fn verify_streams(png: &Png) -> Vec<StreamReport> {
+    let mut reports = vec![];
+
+    let idat: Vec<u8> = png
+        .chunks()
+        .iter()
+        .filter(|c| *c.chunk_type() == ChunkType::IDAT)
+        .flat_map(|c| c.data().iter().copied())
+        .collect();
+    if !idat.is_empty() {
+        let limit = Ihdr::from_png(png)
+            .and_then(|ihdr| ihdr.image_data_len())
+            .unwrap_or(MAX_CHUNK_STREAM_LEN);
+        reports.push(StreamReport {
+            label: "IDAT".to_string(),
+            result: inflate_limited(&idat, limit).map(|d| d.len()),
+        });
+    }
+
+    for (idx, chunk) in png.chunks().iter().enumerate() {
+        if let Some(stream) = chunk_stream(chunk) {
+            reports.push(StreamReport {
+                label: format!("{} #{}", chunk.chunk_type(), idx),
+                result: stream
+                    .and_then(|stream| inflate_limited(stream, MAX_CHUNK_STREAM_LEN))
+                    .map(|d| d.len()),
+            });
+        }
+    }
+
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_inflate() {
+        assert_eq!(inflate(&deflate(b"hello hello")).unwrap(), b"hello hello");
+    }
+
+    #[test]
+    fn test_inflate_bad_checksum() {
+        let mut stream = deflate(b"hello hello");
+        let last = stream.len() - 1;
+        stream[last] ^= 0xff;
+        assert!(inflate(&stream).is_err());
+    }
+
+    #[test]
+    fn test_inflate_truncated() {
+        let stream = deflate(b"hello hello");
+        assert!(matches!(
+            inflate(&stream[..stream.len() - 3]),
+            Err(ZlibError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn test_inflate_trailing_data() {
+        let mut stream = deflate(b"hello hello");
+        stream.extend([1, 2]);
+        assert!(matches!(inflate(&stream), Err(ZlibError::TrailingData(2))));
+    }
+
+    #[test]
+    fn test_ztxt_stream() {
+        let mut data = b"Comment\0\0".to_vec();
+        data.extend(deflate(b"compressed text"));
+        let chunk = Chunk::new(ChunkType::from_str("zTXt").unwrap(), data);
+        let stream = chunk_stream(&chunk).unwrap().unwrap();
+        assert_eq!(inflate(stream).unwrap(), b"compressed text");
+    }
+
+    #[test]
+    fn test_verify_streams_limits() {
+        let chunk = |chunk_type: &str, data: Vec<u8>| {
+            Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data)
+        };
+        // 1x1 8-bit grayscale: one filter byte and one sample.
+        let ihdr = [0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0].to_vec();
+        let mut ztxt = b"Comment\0\0".to_vec();
+        ztxt.extend(deflate(&vec![0; MAX_CHUNK_STREAM_LEN + 1]));
+        let png = Png::from_chunks(vec![
+            chunk("IHDR", ihdr),
+            chunk("IDAT", deflate(&[0; 3])),
+            chunk("zTXt", ztxt),
+        ]);
+
+        let reports = verify_streams(&png);
+        assert!(matches!(reports[0].result, Err(ZlibError::TooLarge(2))));
+        assert!(matches!(
+            reports[1].result,
+            Err(ZlibError::TooLarge(MAX_CHUNK_STREAM_LEN))
+        ));
+    }
+
+    #[test]
+    fn test_uncompressed_itxt_has_no_stream() {
+        let data = b"Comment\0\0\0en\0\0text".to_vec();
+        let chunk = Chunk::new(ChunkType::from_str("iTXt").unwrap(), data);
+        assert!(chunk_stream(&chunk).is_none());
+    }
+}
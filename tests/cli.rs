@@ -0,0 +1,51 @@
+//! The library decodes what the `pngme` binary encodes.
+#![cfg(feature = "cli")]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use pngme::{decode_message, Chunk, ChunkType, Png};
+
+fn carrier(name: &str) -> PathBuf {
+    let chunk = |t: ChunkType| Chunk::new(t, vec![]);
+    let png = Png::from_chunks(vec![chunk(ChunkType::IHDR), chunk(ChunkType::IEND)]);
+    let path = std::env::temp_dir().join(format!("pngme-cli-{}-{}.png", std::process::id(), name));
+    std::fs::write(&path, png.as_bytes()).unwrap();
+    path
+}
+
+fn encode(name: &str, message: &str, flags: &[&str]) -> Vec<u8> {
+    let path = carrier(name);
+    let status = Command::new(env!("CARGO_BIN_EXE_pngme"))
+        .arg("encode")
+        .arg(&path)
+        .args(["ruSt", message])
+        .args(flags)
+        .status()
+        .unwrap();
+    assert!(status.success(), "pngme encode {:?} failed", flags);
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    bytes
+}
+
+#[test]
+fn test_decode_cli_output() {
+    let long = "A secret message! ".repeat(20);
+    let cases: [(&str, &str, &[&str]); 5] = [
+        ("plain", "A secret message!", &[]),
+        ("compressed", &long, &["--compress"]),
+        ("split", &long, &["--split", "64"]),
+        ("compressed-split", &long, &["--compress", "--split", "20"]),
+        ("looks-compressed", "PNGZ!hello", &[]),
+    ];
+    for (name, message, flags) in cases {
+        let png = encode(name, message, flags);
+        assert_eq!(
+            decode_message(&png, "ruSt").unwrap().as_deref(),
+            Some(message.as_bytes()),
+            "{:?}",
+            flags
+        );
+    }
+}